@@ -1,40 +1,53 @@
-use std::collections::HashMap;
-use rust_cli_roguelike::common::protocol::{GameState, NetworkPlayer, PlayerId, ClientMessage, ServerMessage};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+use rust_cli_roguelike::common::protocol::{GameState, NetworkPlayer, PlayerId, ClientMessage, ServerMessage, TileModification, decode_tiles};
 use rust_cli_roguelike::common::game_logic::{GameLogic, GameChunkManager};
+use rust_cli_roguelike::common::chunk::{Chunk, ChunkCoord};
+use rust_cli_roguelike::common::constants::{Difficulty, GameConstants};
+use rust_cli_roguelike::common::item::Item;
+
+use crate::config::{ClientConfig, TileZoom};
+use crate::lan::LanBrowser;
+use crate::network::describe_connect_error;
+use crate::notify::{self, NotifyEvent};
+use tokio::task::JoinHandle;
 
 // Re-export common types for use by other client modules
 pub use rust_cli_roguelike::common::protocol::{CurrentScreen, MapType};
 pub use rust_cli_roguelike::common::game_logic::{Tile, GameMap, Player};
 
-// Helper function to parse local coordinate strings like "0,0"
-fn parse_local_coords(coord_str: &str) -> Result<(i32, i32), ()> {
-    let parts: Vec<&str> = coord_str.split(',').collect();
-    if parts.len() == 2 {
-        if let (Ok(x), Ok(y)) = (parts[0].parse::<i32>(), parts[1].parse::<i32>()) {
-            return Ok((x, y));
-        }
-    }
-    Err(())
-}
-
 // Forward declaration - the actual NetworkClient is defined in network.rs
 pub struct NetworkClient {
     pub sender: tokio::sync::mpsc::UnboundedSender<ClientMessage>,
     pub receiver: tokio::sync::mpsc::UnboundedReceiver<ServerMessage>,
     pub player_id: Option<PlayerId>,
     pub game_state: Option<GameState>,
-    pub messages: Vec<String>,
+    pub messages: Vec<Message>,
     pub multiplayer_chunks: HashMap<(i32, i32), HashMap<(i32, i32), Tile>>, // For multiplayer chunk storage
     pub dungeon_map: Option<GameMap>, // Store the current dungeon map from server
+    pub notifications: Vec<NotifyEvent>, // Events for the App to cue, drained each update
+    pub chat_history: Vec<(String, String)>, // Chat from before we joined, for the App to take
+    pub receive_task: JoinHandle<()>, // Reads from the socket; stopped when the client is dropped
+}
+
+impl Drop for NetworkClient {
+    fn drop(&mut self) {
+        // The send task finishes by itself once `sender` is gone and closes the socket
+        self.receive_task.abort();
+    }
 }
 
 impl NetworkClient {
-    pub fn process_messages(&mut self) {
+    /// Handle everything the server sent since the last call; returns whether anything arrived
+    pub fn process_messages(&mut self) -> bool {
+        let mut received = false;
         while let Ok(msg) = self.receiver.try_recv() {
+            received = true;
             match msg {
                 ServerMessage::Connected { player_id } => {
                     self.player_id = Some(player_id);
-                    self.messages.push("Connected to server!".to_string());
+                    self.messages.push(Message::new(MessageKind::System, "Connected to server!"));
                 }
                 ServerMessage::GameState { state } => {
                     self.game_state = Some(state);
@@ -42,40 +55,63 @@ impl NetworkClient {
                 ServerMessage::PlayerMoved { .. } => {
                     // Game state will be updated in the next GameState message
                 }
+                ServerMessage::TileChanged { x, y, tile } => {
+                    // Patch the cached chunk; chunks we don't have yet will arrive already modified
+                    let chunk_coord = ChunkCoord::from_world_pos(x, y);
+                    if let Some(chunk_tiles) = self.multiplayer_chunks.get_mut(&(chunk_coord.x, chunk_coord.y)) {
+                        chunk_tiles.insert(Chunk::world_to_local(x, y), tile);
+                    }
+                }
                 ServerMessage::PlayerJoined { player_id: _, player } => {
-                    self.messages.push(format!("{} joined the game!", player.name));
+                    self.messages.push(Message::new(MessageKind::System, format!("{} joined the game!", player.name)));
+                    self.notifications.push(NotifyEvent::PlayerJoined);
                 }
                 ServerMessage::PlayerLeft { player_id } => {
-                    self.messages.push(format!("Player {} left the game!", player_id));
+                    self.messages.push(Message::new(MessageKind::System, format!("Player {} left the game!", player_id)));
                 }
                 ServerMessage::Error { message } => {
-                    self.messages.push(format!("Error: {}", message));
+                    self.messages.push(Message::new(MessageKind::Error, format!("Error: {}", message)));
                 }
                 ServerMessage::Message { text } => {
-                    self.messages.push(text);
+                    self.messages.push(Message::new(MessageKind::Interaction, text));
                 }
                 ServerMessage::ChatMessage { player_name, message } => {
                     // Store chat message separately from game messages
                     // This will be handled by the App struct
-                    self.messages.push(format!("[CHAT] {}: {}", player_name, message));
+                    self.messages.push(Message::new(MessageKind::System, format!("[CHAT] {}: {}", player_name, message)));
+                }
+                ServerMessage::ChatHistory { messages } => {
+                    self.chat_history.extend(messages);
                 }
                 ServerMessage::ChunkData { chunks } => {
                     // Handle received chunk data from server
                     for chunk in chunks {
-                        let mut chunk_tiles = HashMap::new();
-                        for (local_coord_str, tile) in chunk.tiles {
-                            if let Ok(coords) = parse_local_coords(&local_coord_str) {
-                                chunk_tiles.insert(coords, tile);
+                        match decode_tiles(&chunk.tiles) {
+                            Ok(chunk_tiles) => {
+                                self.multiplayer_chunks.insert((chunk.chunk_x, chunk.chunk_y), chunk_tiles);
                             }
+                            Err(err) => self.messages.push(Message::new(
+                                MessageKind::Error,
+                                format!("Ignored chunk ({}, {}) from the server: {}", chunk.chunk_x, chunk.chunk_y, err),
+                            )),
                         }
-                        self.multiplayer_chunks.insert((chunk.chunk_x, chunk.chunk_y), chunk_tiles);
                     }
                 }
                 ServerMessage::DungeonData { dungeon_map } => {
                     // Convert NetworkGameMap to GameMap and store it
-                    let game_map = GameLogic::network_map_to_game(&dungeon_map);
-                    self.dungeon_map = Some(game_map);
-                    self.messages.push("Received dungeon map from server".to_string());
+                    match GameLogic::network_map_to_game(&dungeon_map) {
+                        Ok(game_map) => {
+                            self.dungeon_map = Some(game_map);
+                            self.messages.push(Message::new(MessageKind::System, "Received dungeon map from server"));
+                        }
+                        Err(err) => self.messages.push(Message::new(
+                            MessageKind::Error,
+                            format!("Ignored the dungeon map from the server: {}", err),
+                        )),
+                    }
+                }
+                ServerMessage::Unknown { kind } => {
+                    self.messages.push(Message::new(MessageKind::Error, format!("Ignored unknown server message '{}', your client may be outdated.", kind)));
                 }
             }
         }
@@ -84,12 +120,21 @@ impl NetworkClient {
         if self.messages.len() > 10 {
             self.messages.drain(0..self.messages.len() - 10);
         }
+        received
     }
 
     pub fn send_move(&self, dx: i32, dy: i32) {
         let _ = self.sender.send(ClientMessage::Move { dx, dy });
     }
 
+    pub fn send_modify_tile(&self, x: i32, y: i32, modification: TileModification) {
+        let _ = self.sender.send(ClientMessage::ModifyTile { x, y, modification });
+    }
+
+    pub fn send_fast_travel(&self, x: i32, y: i32) {
+        let _ = self.sender.send(ClientMessage::FastTravel { x, y });
+    }
+
     pub fn send_enter_dungeon(&self) {
         let _ = self.sender.send(ClientMessage::EnterDungeon);
     }
@@ -110,15 +155,16 @@ impl NetworkClient {
         let _ = self.sender.send(ClientMessage::Chat { message });
     }
 
-    pub fn send_open_chat(&self) {
-        // Chat is a local UI state, no need to notify server
+    pub fn send_use_item(&self, item: Item) {
+        let _ = self.sender.send(ClientMessage::UseItem { item });
     }
 
-    pub fn send_close_chat(&self) {
-        // Chat is a local UI state, no need to notify server
+    pub fn send_wait(&self) {
+        let _ = self.sender.send(ClientMessage::Wait);
     }
 
-    pub fn disconnect(&self) {
+    /// Say goodbye to the server and shut the connection down
+    pub fn disconnect(self) {
         let _ = self.sender.send(ClientMessage::Disconnect);
     }
 
@@ -131,28 +177,104 @@ impl NetworkClient {
     }
 }
 
+/// What the player got up to this session, printed after the game exits
+#[derive(Debug, Clone, Default)]
+pub struct SessionSummary {
+    pub turns_taken: u32,
+    pub tiles_explored: usize, // Distinct overworld tiles stepped on
+    pub dungeons_entered: u32,
+}
+
+impl fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Session summary: {} turns taken, {} overworld tiles explored, {} dungeons entered",
+            self.turns_taken, self.tiles_explored, self.dungeons_entered
+        )
+    }
+}
+
 pub struct App {
+    pub config: ClientConfig,
+    pub zoom: TileZoom,
     pub current_screen: rust_cli_roguelike::common::protocol::CurrentScreen,
     pub should_quit: bool,
     pub player: rust_cli_roguelike::common::game_logic::Player,
     pub game_map: rust_cli_roguelike::common::game_logic::GameMap,
     pub chunk_manager: Option<GameChunkManager>, // For infinite terrain in single player
-    pub multiplayer_chunks: HashMap<(i32, i32), HashMap<(i32, i32), Tile>>, // For multiplayer chunk storage
-    pub messages: Vec<String>,
+    pub world_seed: u32, // Seed of the single player overworld, reused when leaving dungeons
+    overworld_in_dungeon: Option<GameChunkManager>, // The single player overworld, with its unsaved changes, while in a dungeon
+    pub visited_villages: Vec<(i32, i32)>, // In order of discovery, used for fast travel
+    looted_ruins: HashSet<(i32, i32)>, // Single player ruins whose recall scroll was taken
+    pub fast_travel_selected: usize,
+    pub survival: bool, // Hunger is only tracked in survival mode (single player)
+    pub difficulty: Difficulty, // Single player preset; the server decides in multiplayer
+    pub last_autosave: Instant, // When autosave last checked for changes
+    pub saved_at: Option<Instant>, // Last autosave, for the status bar indicator
+    pub dungeon_prompt_open: bool, // Waiting for y/n after stepping onto a dungeon entrance
+    awaiting_dungeon_map: bool, // Asked the server to enter a dungeon and its map hasn't arrived yet
+    pub session: SessionSummary, // Across every game played since the client started
+    explored_tiles: HashSet<(i32, i32)>, // Overworld tiles counted in the session summary
+    pub dungeon_explored: HashSet<(i32, i32)>, // Tiles of the current dungeon the player has seen
+    pub show_dungeon_minimap: bool,
+    pub show_coordinate_grid: bool, // Debug overlay of world coordinates
+    pub show_name_tags: bool, // Label nearby players with their names
+    pub map_transition: Option<Instant>, // When the map last changed, while it fades in
+    pub messages: Vec<Message>,
+    pub message_filter: Option<MessageKind>, // Only show this kind in the log; everything when None
     pub turn_count: u32,
     pub current_map_type: rust_cli_roguelike::common::protocol::MapType,
     pub game_mode: GameMode,
     pub network_client: Option<NetworkClient>,
+    connection_task: Option<JoinHandle<Result<NetworkClient, String>>>, // Connecting in the background
+    pub lan_browser: Option<LanBrowser>, // Open while the main menu lists LAN games
     pub other_players: HashMap<PlayerId, NetworkPlayer>,
+    pub player_trails: HashMap<PlayerId, VecDeque<(i32, i32)>>, // Recent positions of other players, newest first
     pub main_menu_state: MainMenuState,
     pub server_address: String,
     pub player_name: String,
     // Chat functionality
     pub chat_messages: Vec<(String, String)>, // (player_name, message)
+    pub chat_scroll: usize, // Messages scrolled back from the newest, 0 follows new ones
     pub chat_input: String,
     pub chat_input_mode: bool, // True when actively typing in the chat bar
 }
 
+/// What a log message is about, which decides its color in the message log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Combat,      // Damage and danger
+    System,      // Connection, settings and other out-of-game notices
+    Interaction, // Flavor text from moving and acting in the world
+    Error,       // Something the player tried that didn't work
+}
+
+impl MessageKind {
+    pub const ALL: [MessageKind; 4] = [MessageKind::Combat, MessageKind::System, MessageKind::Interaction, MessageKind::Error];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageKind::Combat => "Combat",
+            MessageKind::System => "System",
+            MessageKind::Interaction => "Interaction",
+            MessageKind::Error => "Error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub text: String,
+    pub kind: MessageKind,
+}
+
+impl Message {
+    pub fn new(kind: MessageKind, text: impl Into<String>) -> Self {
+        Message { text: text.into(), kind }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameMode {
     SinglePlayer,
@@ -162,8 +284,9 @@ pub enum GameMode {
 #[derive(Debug, Clone)]
 pub struct MainMenuState {
     pub selected_option: usize,
-    pub connecting: bool,
+    pub connecting: Option<Instant>, // When the running connection attempt started
     pub connection_error: Option<String>,
+    pub notice: Option<String>, // Shown in the status box, e.g. how the last game ended
     pub username_input_mode: bool,
     pub username_input: String,
 }
@@ -172,8 +295,9 @@ impl MainMenuState {
     pub fn new() -> Self {
         Self {
             selected_option: 0,
-            connecting: false,
+            connecting: None,
             connection_error: None,
+            notice: None,
             username_input_mode: false,
             username_input: String::new(),
         }
@@ -181,78 +305,226 @@ impl MainMenuState {
 }
 
 impl App {
-    pub fn new() -> App {
+    pub fn new(config: ClientConfig) -> App {
+        let difficulty = config.difficulty;
         App {
+            zoom: config.zoom,
+            config,
             current_screen: CurrentScreen::MainMenu,
             should_quit: false,
+            difficulty,
             player: Player {
                 x: 30,
                 y: 15,
-                hp: 20,
-                max_hp: 20,
+                hp: difficulty.starting_hp(),
+                max_hp: difficulty.starting_hp(),
                 symbol: '@',
                 dungeon_entrance_pos: None,
+                hunger: GameConstants::MAX_HUNGER,
+                inventory: Vec::new(),
             },
-            game_map: GameMap {
-                width: 0,
-                height: 0,
-                tiles: HashMap::new(),
-            },
+            game_map: GameMap::empty(),
             chunk_manager: None,
-            multiplayer_chunks: HashMap::new(),
-            messages: vec!["Welcome! Select game mode from the menu.".to_string()],
+            world_seed: 0,
+            overworld_in_dungeon: None,
+            visited_villages: Vec::new(),
+            looted_ruins: HashSet::new(),
+            fast_travel_selected: 0,
+            survival: false,
+            last_autosave: Instant::now(),
+            saved_at: None,
+            dungeon_prompt_open: false,
+            awaiting_dungeon_map: false,
+            session: SessionSummary::default(),
+            explored_tiles: HashSet::new(),
+            dungeon_explored: HashSet::new(),
+            show_dungeon_minimap: false,
+            show_coordinate_grid: false,
+            show_name_tags: false,
+            map_transition: None,
+            messages: vec![Message::new(MessageKind::System, "Welcome! Select game mode from the menu.")],
+            message_filter: None,
             turn_count: 0,
             current_map_type: MapType::Overworld,
             game_mode: GameMode::SinglePlayer,
             network_client: None,
+            connection_task: None,
+            lan_browser: None,
             other_players: HashMap::new(),
+            player_trails: HashMap::new(),
             main_menu_state: MainMenuState::new(),
-            server_address: "127.0.0.1:8080".to_string(),
+            server_address: GameConstants::DEFAULT_SERVER_ADDRESS.to_string(),
             player_name: format!("Player{}", std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_millis() % 10000), // Generate unique default name
             chat_messages: Vec::new(),
+            chat_scroll: 0,
             chat_input: String::new(),
             chat_input_mode: false,
         }
     }
 
     pub fn start_single_player(&mut self) {
+        self.main_menu_state.notice = None;
         self.game_mode = GameMode::SinglePlayer;
         self.current_screen = CurrentScreen::Game;
-        // Initialize infinite terrain with chunk manager
-        let seed = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as u32;
-        self.chunk_manager = Some(GameLogic::create_chunk_manager(seed));
+        // Continue the saved world, or fall back to a throwaway one if it can't be read
+        let (seed, world_error) = match GameLogic::single_player_world_seed() {
+            Ok(seed) => (seed, None),
+            Err(e) => {
+                let seed = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as u32;
+                (seed, Some(format!("Couldn't open the saved world, changes won't carry over to the next session: {}", e)))
+            }
+        };
+        self.world_seed = seed;
+        self.visited_villages.clear(); // Villages and ruins aren't saved, so they start over
+        self.looted_ruins.clear();
+        self.survival = false;
+        self.overworld_in_dungeon = None; // Changes left unsaved by the last game were discarded
+        self.player.max_hp = self.difficulty.starting_hp();
+        self.player.hp = self.player.max_hp;
+        self.player.hunger = GameConstants::MAX_HUNGER;
+        self.player.inventory.clear();
+        let mut chunk_manager = self.load_overworld();
+        let base_spawn = self.config.spawn.unwrap_or_else(GameLogic::get_overworld_spawn_position);
+        let (spawn_x, spawn_y) = GameLogic::find_safe_overworld_spawn(&mut chunk_manager, base_spawn);
+        self.player.x = spawn_x;
+        self.player.y = spawn_y;
+        self.chunk_manager = Some(chunk_manager);
         // Keep the old game_map empty for multiplayer compatibility
-        self.game_map = GameMap {
-            width: 0,
-            height: 0,
-            tiles: HashMap::new(),
+        self.game_map = GameMap::empty();
+        self.messages = vec![Message::new(MessageKind::System, "Welcome to the infinite overworld! Explore and discover new terrain as you move.")];
+        self.messages.extend(world_error.map(|error| Message::new(MessageKind::Error, error)));
+    }
+
+    /// Main menu: switch to the next single player difficulty preset
+    pub fn cycle_difficulty(&mut self) {
+        self.difficulty = self.difficulty.next();
+    }
+
+    /// Single player with hunger: food has to be found or gathered to stay alive
+    pub fn start_survival(&mut self) {
+        self.start_single_player();
+        self.survival = true;
+        self.player.inventory = vec![Item::Food; GameConstants::STARTING_FOOD];
+        self.messages.push(Message::new(MessageKind::System, "Survival mode: keep an eye on your hunger. Chop trees and visit villages to find food."));
+    }
+
+    /// Connect to the server in the background so the menu stays responsive
+    pub fn start_connecting(&mut self) {
+        if self.connection_task.is_some() {
+            return;
+        }
+        let address = self.server_address.clone();
+        let player_name = self.player_name.clone();
+        let room = self.config.room.clone();
+        let timeout = Duration::from_secs(self.config.connect_timeout_secs);
+        let retries = self.config.connect_retries;
+        self.main_menu_state.connecting = Some(Instant::now());
+        self.main_menu_state.connection_error = None;
+
+        self.connection_task = Some(tokio::spawn(async move {
+            let mut backoff = Duration::from_millis(GameConstants::CONNECT_RETRY_BACKOFF_MS);
+            let mut attempt = 0;
+            loop {
+                let error = match tokio::time::timeout(timeout, NetworkClient::connect(&address, player_name.clone(), room.clone())).await {
+                    Ok(Ok(client)) => return Ok(client),
+                    Ok(Err(e)) => describe_connect_error(&*e),
+                    Err(_) => format!("timed out after {}s", timeout.as_secs()),
+                };
+                if attempt >= retries {
+                    return Err(format!("Failed to connect to {}: {}", address, error));
+                }
+                attempt += 1;
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }));
+    }
+
+    /// Start listening for servers announcing themselves on the local network
+    pub fn open_lan_browser(&mut self) {
+        match LanBrowser::open() {
+            Ok(browser) => {
+                self.lan_browser = Some(browser);
+                self.main_menu_state.connection_error = None;
+            }
+            Err(e) => {
+                self.main_menu_state.connection_error = Some(format!(
+                    "Couldn't listen for LAN games on port {}: {}",
+                    GameConstants::LAN_DISCOVERY_PORT, e
+                ));
+            }
+        }
+    }
+
+    pub fn close_lan_browser(&mut self) {
+        self.lan_browser = None;
+    }
+
+    /// Connect to the LAN server picked in the list
+    pub fn join_selected_lan_server(&mut self) {
+        let Some(server) = self.lan_browser.as_ref().and_then(LanBrowser::selected_server) else {
+            return;
+        };
+        self.server_address = server.address.to_string();
+        self.lan_browser = None;
+        self.start_connecting();
+    }
+
+    pub fn cancel_connecting(&mut self) {
+        if let Some(task) = self.connection_task.take() {
+            task.abort();
+            self.main_menu_state.connecting = None;
+            self.main_menu_state.connection_error = Some("Connection cancelled.".to_string());
+        }
+    }
+
+    /// Start multiplayer once a background connection attempt has finished.
+    /// Returns whether one did.
+    pub async fn poll_connection(&mut self) -> bool {
+        let Some(task) = self.connection_task.take_if(|task| task.is_finished()) else {
+            return false;
         };
-        self.messages = vec!["Welcome to the infinite overworld! Explore and discover new terrain as you move.".to_string()];
+        self.main_menu_state.connecting = None;
+        match task.await {
+            Ok(Ok(client)) => self.start_multiplayer(client),
+            Ok(Err(message)) => self.main_menu_state.connection_error = Some(message),
+            Err(e) => self.main_menu_state.connection_error = Some(format!("Failed to connect: {}", e)),
+        }
+        true
     }
 
     pub fn start_multiplayer(&mut self, network_client: NetworkClient) {
         self.game_mode = GameMode::MultiPlayer;
         self.network_client = Some(network_client);
+        self.visited_villages.clear(); // The server only knows about visits made while connected
+        self.player_trails.clear();
+        self.awaiting_dungeon_map = false;
+        self.clear_chat(); // The room's ChatHistory refills it
         self.current_screen = CurrentScreen::Game;
-        self.messages = vec!["Connected to multiplayer server!".to_string()];
+        self.messages = vec![Message::new(MessageKind::System, "Connected to multiplayer server!")];
         
         // Request initial chunks around the player's spawn position
         self.request_chunks_around_player();
     }
 
-    pub fn process_network_messages(&mut self) {
+    /// Apply pending server updates; returns whether the screen needs redrawing
+    pub fn process_network_messages(&mut self) -> bool {
         let mut game_state_update = None;
         let mut new_messages = Vec::new();
         let mut dungeon_map_update = None;
+        let mut notifications = Vec::new();
+        let mut chat_history = Vec::new();
         
         if let Some(ref mut client) = self.network_client {
-            client.process_messages();
+            if !client.process_messages() {
+                return false;
+            }
             
             // Collect updates without borrowing self
             if let Some(ref game_state) = client.game_state {
@@ -266,32 +538,45 @@ impl App {
             }
             
             // Collect new messages
-            new_messages.extend(client.messages.drain(..));
+            chat_history.append(&mut client.chat_history);
+            new_messages.append(&mut client.messages);
+            notifications.append(&mut client.notifications);
         }
-        
-        // Apply updates
-        if let Some(state) = game_state_update {
-            self.update_from_network_state(&state);
+
+        for event in notifications {
+            notify::notify(&self.config, event);
         }
         
-        // Apply dungeon map update
+        // Apply the dungeon map first; the server sends it just before the
+        // game state that moves us into the dungeon
         if let Some(dungeon_map) = dungeon_map_update {
             self.game_map = dungeon_map;
+            self.awaiting_dungeon_map = false;
             self.chunk_manager = None; // Disable chunk manager in dungeons
-            self.messages.push("Entered dungeon from multiplayer server".to_string());
+            self.messages.push(Message::new(MessageKind::System, "Entered dungeon from multiplayer server"));
+        }
+        
+        // Apply updates
+        if let Some(state) = game_state_update {
+            self.update_from_network_state(&state);
         }
         
+        // Earlier chat comes before anything said since; it's old news, so no mention bell
+        for (player_name, message) in chat_history {
+            self.record_chat(player_name, message);
+        }
+
         // Update messages and extract chat messages
         for message in &new_messages {
-            if let Some(chat_part) = message.strip_prefix("[CHAT] ") {
+            if let Some(chat_part) = message.text.strip_prefix("[CHAT] ") {
                 if let Some(colon_pos) = chat_part.find(": ") {
                     let player_name = chat_part[..colon_pos].to_string();
                     let chat_message = chat_part[colon_pos + 2..].to_string();
-                    self.chat_messages.push((player_name, chat_message));
-                    // Keep only the last 50 chat messages
-                    if self.chat_messages.len() > 50 {
-                        self.chat_messages.drain(0..self.chat_messages.len() - 50);
+                    if player_name != self.player_name
+                        && chat_message.to_lowercase().contains(&self.player_name.to_lowercase()) {
+                        notify::notify(&self.config, NotifyEvent::ChatMention);
                     }
+                    self.record_chat(player_name, chat_message);
                 } else {
                     self.messages.push(message.clone());
                 }
@@ -302,6 +587,7 @@ impl App {
         
         // Keep only the last 10 messages using shared logic
         GameLogic::limit_messages(&mut self.messages, 10);
+        true
     }
 
     fn update_from_network_state(&mut self, state: &GameState) {
@@ -317,20 +603,31 @@ impl App {
                     let old_map_type = self.current_map_type;
                     let new_map_type = network_player.current_map_type;
                     
-                    self.player.x = network_player.x;
-                    self.player.y = network_player.y;
-                    self.player.hp = network_player.hp;
-                    self.player.max_hp = network_player.max_hp;
+                    if network_player.hp < self.player.hp {
+                        notify::notify(&self.config, NotifyEvent::Damage);
+                    }
+
+                    self.player.apply_network_state(network_player);
                     self.current_map_type = new_map_type;
                     
                     // Handle map transitions in multiplayer
                     if old_map_type != new_map_type {
+                        self.map_transition = Some(Instant::now());
                         match new_map_type {
                             MapType::Dungeon => {
-                                // Generate dungeon map when entering
-                                self.game_map = GameLogic::generate_dungeon_map();
+                                // The map itself always comes from the server's DungeonData
+                                self.session.dungeons_entered += 1;
+                                self.dungeon_explored.clear();
                                 self.chunk_manager = None; // Disable chunk manager in dungeons
-                                self.messages.push("You descend into the dungeon...".to_string());
+                                // Normally the map comes just before this update; ask if it didn't
+                                if self.awaiting_dungeon_map {
+                                    client.send_request_dungeon_data();
+                                }
+                                let message = match network_player.dungeon_entrance_pos {
+                                    Some((x, y)) => GameLogic::get_dungeon_entry_message(x, y),
+                                    None => "You descend into the dungeon...".to_string(),
+                                };
+                                self.messages.push(Message::new(MessageKind::Interaction, message));
                             }
                             MapType::Overworld => {
                                 // Re-enable chunk manager when returning to overworld
@@ -338,15 +635,12 @@ impl App {
                                     .duration_since(std::time::UNIX_EPOCH)
                                     .unwrap()
                                     .as_secs() as u32;
-                                self.chunk_manager = Some(GameLogic::create_chunk_manager(seed));
+                                self.chunk_manager = Some(GameLogic::create_chunk_manager(seed, self.config.chunks));
                                 
                                 // Clear the old dungeon map
-                                self.game_map = GameMap {
-                                    width: 0,
-                                    height: 0,
-                                    tiles: HashMap::new(),
-                                };
-                                self.messages.push("You emerge from the dungeon into the overworld.".to_string());
+                                self.game_map = GameMap::empty();
+                                self.awaiting_dungeon_map = false;
+                                self.messages.push(Message::new(MessageKind::Interaction, "You emerge from the dungeon into the overworld."));
                             }
                         }
                     }
@@ -355,7 +649,7 @@ impl App {
         }
         
         // Update other players
-        self.other_players.clear();
+        let previous_players = std::mem::take(&mut self.other_players);
         if let Some(client) = &self.network_client {
             if let Some(player_id) = &client.player_id {
                 for (id, player) in &state.players {
@@ -365,6 +659,26 @@ impl App {
                 }
             }
         }
+        self.update_player_trails(&previous_players);
+    }
+
+    /// Remember where other players just were so the map can draw a fading trail
+    fn update_player_trails(&mut self, previous_players: &HashMap<PlayerId, NetworkPlayer>) {
+        self.player_trails.retain(|id, _| self.other_players.contains_key(id));
+
+        for (id, player) in &self.other_players {
+            let Some(previous) = previous_players.get(id) else {
+                continue;
+            };
+            let trail = self.player_trails.entry(id.clone()).or_default();
+            if previous.current_map_type != player.current_map_type {
+                // Switching maps leaves nothing meaningful behind
+                trail.clear();
+            } else if (previous.x, previous.y) != (player.x, player.y) {
+                trail.push_front((previous.x, previous.y));
+                trail.truncate(GameConstants::PLAYER_TRAIL_LENGTH);
+            }
+        }
     }
     
     pub fn move_player(&mut self, dx: i32, dy: i32) {
@@ -377,10 +691,19 @@ impl App {
                 let new_x = self.player.x + dx;
                 let new_y = self.player.y + dy;
                 
+                // Dungeons are finite, so never send a move past their edge
+                if self.current_map_type == MapType::Dungeon {
+                    if let Err(message) = GameLogic::check_dungeon_move(&self.game_map, new_x, new_y) {
+                        self.messages.push(Message::new(MessageKind::Error, message));
+                        GameLogic::limit_messages(&mut self.messages, 10);
+                        return;
+                    }
+                }
+
                 // Check if the move is valid based on current map type
                 let tile = if self.current_map_type == MapType::Dungeon {
                     // In dungeons, use the dungeon map tiles
-                    self.game_map.tiles.get(&(new_x, new_y)).copied()
+                    self.game_map.tile_at(new_x, new_y)
                 } else {
                     // In overworld, use multiplayer chunks first, then fall back to traditional map
                     self.get_multiplayer_tile(new_x, new_y).or_else(|| 
@@ -394,6 +717,13 @@ impl App {
                         self.player.x = new_x;
                         self.player.y = new_y;
                         self.turn_count += 1;
+                        self.record_session_step();
+                        self.record_village_visit(tile);
+
+                        // The server only sends flavor text, so the entrance prompt is offered here
+                        if tile == Tile::DungeonEntrance && self.config.dungeon_prompt {
+                            self.offer_dungeon_entry();
+                        }
                         
                         // Send move to server
                         if let Some(ref client) = self.network_client {
@@ -405,7 +735,7 @@ impl App {
                             self.request_chunks_around_player();
                         }
                     } else {
-                        self.messages.push(GameLogic::get_blocked_movement_message(tile));
+                        self.messages.push(Message::new(MessageKind::Error, GameLogic::get_blocked_movement_message(tile)));
                     }
                 } else {
                     // Send move anyway in case server has different map state
@@ -420,48 +750,217 @@ impl App {
         }
     }
 
+    /// Let a turn pass without moving, e.g. to let hunger or a co-op partner catch up
+    pub fn wait_turn(&mut self) {
+        self.turn_count += 1;
+        self.record_session_step();
+        match self.game_mode {
+            GameMode::SinglePlayer => {
+                self.messages.push(Message::new(MessageKind::Interaction, "You wait a moment."));
+                self.pass_survival_turn();
+            }
+            GameMode::MultiPlayer => {
+                if let Some(ref client) = self.network_client {
+                    client.send_wait();
+                }
+            }
+        }
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
+    /// Keep stepping in one direction until something blocks the way, the player
+    /// gets hurt or reaches a tile worth stopping at. Every step is an ordinary
+    /// move, so in multiplayer the server gets a short burst of moves.
+    pub fn sprint(&mut self, dx: i32, dy: i32) {
+        let (screen, map_type) = (self.current_screen, self.current_map_type);
+        for _ in 0..GameConstants::MAX_SPRINT_STEPS {
+            let (x, y, hp) = (self.player.x, self.player.y, self.player.hp);
+            self.move_player(dx, dy);
+            if (self.player.x, self.player.y) == (x, y)
+                || self.player.hp < hp
+                || self.dungeon_prompt_open
+                || self.current_screen != screen
+                || self.current_map_type != map_type
+                || self.tile_under_player().is_some_and(GameLogic::stops_sprint)
+            {
+                break;
+            }
+        }
+    }
+
+    /// The tile the player is standing on, if it is known
+    pub fn tile_under_player(&mut self) -> Option<Tile> {
+        let (x, y) = (self.player.x, self.player.y);
+        if self.current_map_type == MapType::Dungeon {
+            self.game_map.tile_at(x, y)
+        } else if self.game_mode == GameMode::MultiPlayer {
+            self.get_multiplayer_tile(x, y)
+        } else {
+            self.chunk_manager.as_mut()?.get_tile(x, y)
+        }
+    }
+
     fn move_player_single(&mut self, dx: i32, dy: i32) {
+        self.ensure_overworld_loaded();
         let new_x = self.player.x + dx;
         let new_y = self.player.y + dy;
-        
-        // Use chunk manager if available (infinite terrain), otherwise use traditional map
-        let tile = if let Some(ref mut chunk_manager) = self.chunk_manager {
-            chunk_manager.get_tile(new_x, new_y)
+
+        // Dungeons are finite and blocked at their edge; the overworld only by impassable terrain
+        let destination = if self.current_map_type == MapType::Dungeon {
+            GameLogic::check_dungeon_move(&self.game_map, new_x, new_y).map(Some)
+        } else if let Some(ref mut chunk_manager) = self.chunk_manager {
+            GameLogic::check_overworld_move(chunk_manager, new_x, new_y)
         } else {
-            self.game_map.tiles.get(&(new_x, new_y)).copied()
+            return; // ensure_overworld_loaded always restores the overworld
         };
-        
-        if let Some(tile) = tile {
-            if GameLogic::is_movement_valid(tile) {
+
+        match destination {
+            Ok(tile) => {
                 self.player.x = new_x;
                 self.player.y = new_y;
                 self.turn_count += 1;
-                
-                // Add flavor text for tile interactions
-                if let Some(message) = GameLogic::get_tile_interaction_message(tile) {
-                    self.messages.push(message);
+                self.record_session_step();
+                if let Some(tile) = tile {
+                    self.record_village_visit(tile);
+                    self.search_landmark(tile);
+
+                    // Add flavor text for tile interactions
+                    if tile == Tile::DungeonEntrance && self.config.dungeon_prompt {
+                        self.offer_dungeon_entry();
+                    } else if let Some(message) = GameLogic::get_tile_interaction_message(tile) {
+                        self.messages.push(Message::new(MessageKind::Interaction, message));
+                    }
                 }
-            } else {
-                self.messages.push(GameLogic::get_blocked_movement_message(tile));
-            }
-        } else {
-            // Empty space - allow movement in infinite terrain
-            if self.chunk_manager.is_some() {
-                self.player.x = new_x;
-                self.player.y = new_y;
-                self.turn_count += 1;
-            } else {
-                self.messages.push("You can't move there.".to_string());
+                self.pass_survival_turn();
             }
+            Err(message) => self.messages.push(Message::new(MessageKind::Error, message)),
         }
         
         // Keep only the last 10 messages
         GameLogic::limit_messages(&mut self.messages, 10);
     }
     
+    pub fn toggle_dungeon_minimap(&mut self) {
+        self.show_dungeon_minimap = !self.show_dungeon_minimap;
+    }
+
+    pub fn toggle_name_tags(&mut self) {
+        self.show_name_tags = !self.show_name_tags;
+    }
+
+    /// Mark the dungeon tiles around the player as explored for the minimap
+    pub fn reveal_dungeon_around_player(&mut self) {
+        if self.current_map_type != MapType::Dungeon {
+            return;
+        }
+        let radius = GameConstants::DUNGEON_REVEAL_RADIUS;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let (x, y) = (self.player.x + dx, self.player.y + dy);
+                if GameLogic::is_within_dungeon_bounds(&self.game_map, x, y) {
+                    self.dungeon_explored.insert((x, y));
+                }
+            }
+        }
+    }
+
+    /// Debug aid: mark the whole current dungeon as explored so its layout
+    /// can be checked on the minimap without walking it
+    pub fn reveal_whole_dungeon(&mut self) {
+        if !self.config.debug || self.current_map_type != MapType::Dungeon {
+            return;
+        }
+        self.dungeon_explored.extend(self.game_map.tiles.keys());
+        self.show_dungeon_minimap = true;
+        self.messages.push(Message::new(MessageKind::System, "[debug] Revealed the whole dungeon."));
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
+    /// How far the map has faded in after a transition, from 0.0 to 1.0,
+    /// or None when no transition is running
+    pub fn map_transition_progress(&self) -> Option<f32> {
+        let duration = Duration::from_millis(GameConstants::MAP_TRANSITION_MS);
+        self.map_transition.map(|started| (started.elapsed().as_secs_f32() / duration.as_secs_f32()).min(1.0))
+    }
+
+    /// End a finished transition. Returns whether one is running, so the
+    /// fade gets redrawn every frame and once more at full brightness.
+    pub fn animate_map_transition(&mut self) -> bool {
+        if self.map_transition_progress().is_some_and(|progress| progress >= 1.0) {
+            self.map_transition = None;
+            return true;
+        }
+        self.map_transition.is_some()
+    }
+
+    /// Debug aid: label world coordinates across the map
+    pub fn toggle_coordinate_grid(&mut self) {
+        if self.config.debug {
+            self.show_coordinate_grid = !self.show_coordinate_grid;
+        }
+    }
+
+    /// Count a step towards the end of session summary
+    fn record_session_step(&mut self) {
+        self.session.turns_taken += 1;
+        if self.current_map_type == MapType::Overworld {
+            self.explored_tiles.insert((self.player.x, self.player.y));
+            self.session.tiles_explored = self.explored_tiles.len();
+        }
+    }
+
+    /// Ask whether to go down the entrance the player just stepped onto
+    fn offer_dungeon_entry(&mut self) {
+        self.dungeon_prompt_open = true;
+        self.messages.push(Message::new(MessageKind::Interaction, "A dark dungeon entrance. Enter it? (y/n)"));
+    }
+
+    /// Answer the dungeon entrance prompt; 'e' still works after saying no
+    pub fn answer_dungeon_prompt(&mut self, enter: bool) {
+        self.dungeon_prompt_open = false;
+        if enter {
+            self.enter_dungeon();
+        } else {
+            self.messages.push(Message::new(MessageKind::Interaction, "You can press 'e' to enter later."));
+            GameLogic::limit_messages(&mut self.messages, 10);
+        }
+    }
+
+    /// The single player overworld for the current seed, with the configured biomes.
+    /// Coming back from a dungeon picks up the overworld left behind, unsaved changes included
+    fn load_overworld(&mut self) -> GameChunkManager {
+        if let Some(chunk_manager) = self.overworld_in_dungeon.take() {
+            return chunk_manager;
+        }
+        let mut chunk_manager = GameLogic::create_persistent_chunk_manager(self.world_seed, self.config.chunks);
+        chunk_manager.set_biomes(self.config.biomes);
+        // Changes are only written by saving, so quitting without saving discards them
+        chunk_manager.set_manual_saves(true);
+        chunk_manager
+    }
+
+    /// The single player overworld is only ever drawn from the chunk manager, so
+    /// if a state bug dropped it, reload the world rather than show a blank map
+    pub fn ensure_overworld_loaded(&mut self) {
+        if self.game_mode != GameMode::SinglePlayer
+            || self.current_map_type != MapType::Overworld
+            || self.chunk_manager.is_some()
+        {
+            return;
+        }
+        let chunk_manager = self.load_overworld();
+        self.chunk_manager = Some(chunk_manager);
+        self.messages.push(Message::new(
+            MessageKind::Error,
+            "Internal error: the overworld was not loaded. It has been reloaded from the saved world.",
+        ));
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
     pub fn enter_dungeon(&mut self) {
         match self.game_mode {
             GameMode::SinglePlayer => {
+                self.ensure_overworld_loaded();
                 // Check for dungeon entrance using chunk manager if available
                 let at_entrance = if let Some(ref mut chunk_manager) = self.chunk_manager {
                     GameLogic::is_at_chunk_dungeon_entrance(chunk_manager, self.player.x, self.player.y)
@@ -474,22 +973,31 @@ impl App {
                     let entrance_pos = (self.player.x, self.player.y);
                     self.player.dungeon_entrance_pos = Some(entrance_pos);
                     
-                    // Generate a unique dungeon based on entrance position
-                    self.game_map = GameLogic::generate_dungeon_map_for_entrance(entrance_pos.0, entrance_pos.1);
-                    self.chunk_manager = None; // Disable chunk manager in dungeons
+                    // Generate a unique dungeon based on entrance position, unless a seed is forced
+                    let seed_override = self.config.dungeon_seed;
+                    self.game_map = GameLogic::generate_dungeon_map(entrance_pos.0, entrance_pos.1, seed_override);
+                    // Keep the overworld aside rather than saving it, so leaving it is no save point
+                    self.overworld_in_dungeon = self.chunk_manager.take();
                     let (spawn_x, spawn_y) = GameLogic::get_safe_dungeon_spawn_position(&self.game_map);
                     self.player.x = spawn_x;
                     self.player.y = spawn_y;
                     self.current_map_type = MapType::Dungeon;
-                    self.messages.push("You descend into the dungeon...".to_string());
+                    self.map_transition = Some(Instant::now());
+                    self.dungeon_explored.clear();
+                    self.session.dungeons_entered += 1;
+                    match seed_override {
+                        Some(seed) => self.messages.push(Message::new(MessageKind::System, format!("You enter the dungeon with seed {}.", seed))),
+                        None => self.messages.push(Message::new(MessageKind::Interaction, GameLogic::get_dungeon_entry_message(entrance_pos.0, entrance_pos.1))),
+                    }
                 } else {
-                    self.messages.push("You're not at a dungeon entrance.".to_string());
+                    self.messages.push(Message::new(MessageKind::Error, "You're not at a dungeon entrance."));
                 }
             }
             GameMode::MultiPlayer => {
                 if let Some(ref client) = self.network_client {
                     client.send_enter_dungeon();
                     // The server will automatically send dungeon data when we enter
+                    self.awaiting_dungeon_map = true;
                 }
             }
         }
@@ -501,34 +1009,12 @@ impl App {
                 if self.current_map_type == MapType::Dungeon {
                     // Check if player is at a dungeon exit
                     if GameLogic::is_at_dungeon_exit(&self.game_map, self.player.x, self.player.y) {
-                        // Re-enable infinite terrain when returning to overworld
-                        let seed = std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs() as u32;
-                        self.chunk_manager = Some(GameLogic::create_chunk_manager(seed));
-                        
-                        // Clear the old finite map
-                        self.game_map = GameMap {
-                            width: 0,
-                            height: 0,
-                            tiles: HashMap::new(),
-                        };
-                        
-                        // Use stored entrance position or fall back to default spawn
-                        let (spawn_x, spawn_y) = self.player.dungeon_entrance_pos
-                            .unwrap_or_else(|| GameLogic::get_overworld_spawn_position());
-                        
-                        self.player.x = spawn_x;
-                        self.player.y = spawn_y;
-                        self.player.dungeon_entrance_pos = None; // Clear the stored entrance position
-                        self.current_map_type = MapType::Overworld;
-                        self.messages.push("You emerge from the dungeon into the infinite overworld.".to_string());
+                        self.leave_dungeon("You emerge from the dungeon into the infinite overworld.");
                     } else {
-                        self.messages.push("You must be at the dungeon entrance (marked with '<') to exit.".to_string());
+                        self.messages.push(Message::new(MessageKind::Error, "You must be at the dungeon entrance (marked with '<') to exit."));
                     }
                 } else {
-                    self.messages.push("You're not in a dungeon.".to_string());
+                    self.messages.push(Message::new(MessageKind::Error, "You're not in a dungeon."));
                 }
             }
             GameMode::MultiPlayer => {
@@ -539,6 +1025,296 @@ impl App {
         }
     }
     
+    /// Single player: return from the dungeon to the entrance it was entered from
+    fn leave_dungeon(&mut self, message: &str) {
+        // Re-enable infinite terrain when returning to overworld, reloading
+        // the same world so saved modifications are picked up again
+        let mut chunk_manager = self.load_overworld();
+        
+        // Clear the old finite map
+        self.game_map = GameMap::empty();
+        
+        // Use stored entrance position or fall back to default spawn
+        let target = self.player.dungeon_entrance_pos
+            .unwrap_or_else(|| self.config.spawn.unwrap_or_else(GameLogic::get_overworld_spawn_position));
+        let (spawn_x, spawn_y) = GameLogic::find_safe_overworld_spawn(&mut chunk_manager, target);
+        self.chunk_manager = Some(chunk_manager);
+        
+        self.player.x = spawn_x;
+        self.player.y = spawn_y;
+        self.player.dungeon_entrance_pos = None; // Clear the stored entrance position
+        self.current_map_type = MapType::Overworld;
+        self.map_transition = Some(Instant::now());
+        self.messages.push(Message::new(MessageKind::Interaction, message));
+    }
+
+    /// Read a recall scroll from the inventory to escape the current dungeon
+    pub fn read_recall_scroll(&mut self) {
+        match self.game_mode {
+            GameMode::SinglePlayer => {
+                match GameLogic::read_recall_scroll(&mut self.player.inventory, self.current_map_type) {
+                    Ok(()) => {
+                        self.close_inventory();
+                        self.leave_dungeon("You read the recall scroll and are pulled back to the surface.");
+                    }
+                    Err(message) => self.messages.push(Message::new(MessageKind::Error, message)),
+                }
+            }
+            GameMode::MultiPlayer => {
+                // The server checks the scroll and the dungeon, and moves us if both are fine
+                self.close_inventory();
+                if let Some(ref client) = self.network_client {
+                    client.send_use_item(Item::RecallScroll);
+                }
+            }
+        }
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
+    /// Look through the spyglass at the dungeon behind the entrance we're standing on.
+    /// Dungeons are deterministic, so this works the same in multiplayer without asking the server.
+    pub fn scout_dungeon(&mut self) {
+        let (x, y) = (self.player.x, self.player.y);
+        let tile = match self.game_mode {
+            GameMode::SinglePlayer => self.chunk_manager.as_mut().and_then(|chunk_manager| chunk_manager.get_tile(x, y)),
+            GameMode::MultiPlayer => self.get_multiplayer_tile(x, y),
+        };
+        let entrance = (self.current_map_type == MapType::Overworld && tile == Some(Tile::DungeonEntrance)).then_some((x, y));
+        let seed_override = match self.game_mode {
+            GameMode::SinglePlayer => self.config.dungeon_seed,
+            GameMode::MultiPlayer => None,
+        };
+
+        match GameLogic::scout_dungeon(&self.player.inventory, entrance, seed_override) {
+            Ok(message) => {
+                self.close_inventory();
+                self.messages.push(Message::new(MessageKind::Interaction, message));
+            }
+            Err(message) => self.messages.push(Message::new(MessageKind::Error, message)),
+        }
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
+    /// Chop a tree or lay a road on the tile the player is standing on
+    pub fn modify_tile(&mut self, modification: TileModification) {
+        if self.current_map_type != MapType::Overworld {
+            self.messages.push(Message::new(MessageKind::Error, "You can't reshape the dungeon."));
+            return;
+        }
+
+        let (x, y) = (self.player.x, self.player.y);
+        match self.game_mode {
+            GameMode::SinglePlayer => {
+                if let Some(ref mut chunk_manager) = self.chunk_manager {
+                    let result = chunk_manager.get_tile(x, y)
+                        .ok_or_else(|| "There is nothing here to work on.".to_string())
+                        .and_then(|tile| GameLogic::apply_tile_modification(tile, modification));
+
+                    match result {
+                        Ok(new_tile) => {
+                            chunk_manager.set_tile(x, y, new_tile);
+                            self.turn_count += 1;
+                            self.messages.push(Message::new(MessageKind::Interaction, GameLogic::get_tile_modification_message(modification)));
+
+                            if self.survival && modification == TileModification::ChopTree && GameLogic::tree_has_food(x, y) {
+                                self.player.inventory.push(Item::Food);
+                                self.messages.push(Message::new(MessageKind::Interaction, "You find some berries among the branches."));
+                            }
+                            self.pass_survival_turn();
+                        }
+                        Err(err) => self.messages.push(Message::new(MessageKind::Error, err)),
+                    }
+                }
+            }
+            GameMode::MultiPlayer => {
+                // The server validates the change and broadcasts the new tile to everyone
+                if let Some(ref client) = self.network_client {
+                    client.send_modify_tile(x, y, modification);
+                }
+            }
+        }
+
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
+    /// Step the message log through showing everything, then each kind on its own
+    pub fn cycle_message_filter(&mut self) {
+        let next = match self.message_filter {
+            None => Some(0),
+            Some(kind) => MessageKind::ALL.iter().position(|&k| k == kind).map(|i| i + 1),
+        };
+        self.message_filter = next.and_then(|i| MessageKind::ALL.get(i).copied());
+    }
+
+    /// Switch to the next map zoom level
+    pub fn cycle_zoom(&mut self) {
+        self.zoom = self.zoom.next();
+        self.messages.push(Message::new(MessageKind::System, format!("Map zoom: {}", self.zoom.name())));
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
+    /// Single player world changes that quitting without saving would lose
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.chunk_manager.iter()
+            .chain(self.overworld_in_dungeon.iter())
+            .any(|chunk_manager| chunk_manager.has_unsaved_changes())
+    }
+
+    /// Flush modified overworld chunks to disk so they survive the chunk manager being dropped
+    /// Returns whether everything was saved
+    pub fn save_overworld_chunks(&mut self) -> bool {
+        for chunk_manager in self.chunk_manager.iter_mut().chain(self.overworld_in_dungeon.iter_mut()) {
+            if let Err(e) = chunk_manager.save_dirty_chunks() {
+                self.messages.push(Message::new(MessageKind::Error, format!("Failed to save world changes: {}", e)));
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Write single player world changes every `autosave_secs` so a crash loses
+    /// little. Returns whether the status bar needs redrawing.
+    pub fn autosave_if_due(&mut self) -> bool {
+        let interval = Duration::from_secs(self.config.autosave_secs);
+        let indicator_expired = self.saved_at.is_some_and(|saved| {
+            saved.elapsed() >= Duration::from_millis(GameConstants::SAVED_INDICATOR_MS)
+        });
+        if indicator_expired {
+            self.saved_at = None;
+        }
+        if self.config.autosave_secs == 0
+            || self.game_mode != GameMode::SinglePlayer
+            || self.last_autosave.elapsed() < interval
+        {
+            return indicator_expired;
+        }
+
+        self.last_autosave = Instant::now();
+        if !self.has_unsaved_changes() || !self.save_overworld_chunks() {
+            return indicator_expired;
+        }
+        self.saved_at = Some(Instant::now());
+        true
+    }
+
+    /// Save single player progress and quit; stays in game if saving fails
+    pub fn save_and_quit(&mut self) {
+        if self.save_overworld_chunks() {
+            self.should_quit = true;
+        } else {
+            self.current_screen = CurrentScreen::Game;
+        }
+    }
+
+    /// Single player: the first visit to some ruins turns up a recall scroll, and
+    /// towers have a spyglass for anyone who doesn't carry one yet
+    fn search_landmark(&mut self, tile: Tile) {
+        let position = (self.player.x, self.player.y);
+        if tile == Tile::Ruins && GameLogic::ruins_have_scroll(position.0, position.1) && self.looted_ruins.insert(position) {
+            self.player.inventory.push(Item::RecallScroll);
+            self.messages.push(Message::new(MessageKind::Interaction, "You find a recall scroll among the ruins."));
+        } else if tile == Tile::Tower && !self.player.inventory.contains(&Item::Spyglass) {
+            self.player.inventory.push(Item::Spyglass);
+            self.messages.push(Message::new(MessageKind::Interaction, "You find an old spyglass at the top of the tower."));
+        }
+    }
+
+    /// Remember a village the player has reached so it can be fast travelled to
+    fn record_village_visit(&mut self, tile: Tile) {
+        let position = (self.player.x, self.player.y);
+        if tile == Tile::Village && !self.visited_villages.contains(&position) {
+            self.visited_villages.push(position);
+            if self.survival {
+                self.player.inventory.push(Item::Food);
+                self.messages.push(Message::new(MessageKind::Interaction, "The villagers share some food with you."));
+            }
+        }
+    }
+
+    /// Per-turn survival upkeep for single player; starving to death ends the game
+    fn pass_survival_turn(&mut self) {
+        if !self.survival {
+            return;
+        }
+
+        if let Some(message) = GameLogic::apply_hunger(&mut self.player, self.difficulty, self.turn_count) {
+            self.messages.push(Message::new(MessageKind::Combat, message));
+        }
+
+        if self.player.hp <= 0 {
+            self.save_overworld_chunks();
+            self.survival = false;
+            self.current_screen = CurrentScreen::MainMenu;
+            self.messages = vec![Message::new(MessageKind::Combat, "You starved to death. Game over.")];
+            self.main_menu_state.notice = Some(format!("You starved to death after {} turns.", self.turn_count));
+        }
+    }
+
+    pub fn eat_food(&mut self) {
+        match GameLogic::eat_food(&mut self.player) {
+            Ok(message) => self.messages.push(Message::new(MessageKind::Interaction, message)),
+            Err(message) => self.messages.push(Message::new(MessageKind::Error, message)),
+        }
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
+    pub fn open_fast_travel(&mut self) {
+        if self.current_map_type != MapType::Overworld {
+            self.messages.push(Message::new(MessageKind::Error, "You can only fast travel from the overworld."));
+        } else if self.visited_villages.is_empty() {
+            self.messages.push(Message::new(MessageKind::Error, "You haven't visited any villages yet."));
+        } else {
+            self.fast_travel_selected = self.fast_travel_selected.min(self.visited_villages.len() - 1);
+            self.current_screen = CurrentScreen::FastTravel;
+        }
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
+    pub fn close_fast_travel(&mut self) {
+        self.current_screen = CurrentScreen::Game;
+    }
+
+    pub fn select_previous_village(&mut self) {
+        self.fast_travel_selected = self.fast_travel_selected.saturating_sub(1);
+    }
+
+    pub fn select_next_village(&mut self) {
+        if self.fast_travel_selected + 1 < self.visited_villages.len() {
+            self.fast_travel_selected += 1;
+        }
+    }
+
+    /// Travel to the selected village; the server validates the trip in multiplayer
+    pub fn fast_travel_to_selected(&mut self) {
+        self.current_screen = CurrentScreen::Game;
+        let Some(&(x, y)) = self.visited_villages.get(self.fast_travel_selected) else {
+            return;
+        };
+
+        match self.game_mode {
+            GameMode::SinglePlayer => {
+                if let Some(ref mut chunk_manager) = self.chunk_manager {
+                    // Regenerates the destination area if it was unloaded
+                    chunk_manager.update_player_position(x, y);
+                    if chunk_manager.get_tile(x, y) == Some(Tile::Village) {
+                        (self.player.x, self.player.y) = GameLogic::find_safe_overworld_spawn(chunk_manager, (x, y));
+                        self.turn_count += 1;
+                        self.messages.push(Message::new(MessageKind::Interaction, GameLogic::get_fast_travel_message(x, y)));
+                        self.pass_survival_turn();
+                    } else {
+                        self.messages.push(Message::new(MessageKind::Error, "That village is no longer there."));
+                    }
+                }
+            }
+            GameMode::MultiPlayer => {
+                if let Some(ref client) = self.network_client {
+                    client.send_fast_travel(x, y);
+                }
+            }
+        }
+        GameLogic::limit_messages(&mut self.messages, 10);
+    }
+
     pub fn open_inventory(&mut self) {
         self.current_screen = CurrentScreen::Inventory;
         if self.game_mode == GameMode::MultiPlayer {
@@ -557,6 +1333,36 @@ impl App {
         }
     }
 
+    /// Forget the chat of the room we were in
+    fn clear_chat(&mut self) {
+        self.chat_messages.clear();
+        self.chat_scroll = 0;
+    }
+
+    /// Keep a chat message within the configured history. While scrolled back,
+    /// the view stays on the same messages instead of jumping as new ones arrive.
+    pub fn record_chat(&mut self, player_name: String, message: String) {
+        self.chat_messages.push((player_name, message));
+        if self.chat_scroll > 0 {
+            self.chat_scroll += 1;
+        }
+        let history = self.config.chat_history;
+        if self.chat_messages.len() > history {
+            self.chat_messages.drain(0..self.chat_messages.len() - history);
+        }
+        self.chat_scroll = self.chat_scroll.min(self.chat_messages.len().saturating_sub(1));
+    }
+
+    /// Page through the chat history: back towards older messages or forward to the newest
+    pub fn scroll_chat(&mut self, back: bool) {
+        let oldest = self.chat_messages.len().saturating_sub(1);
+        self.chat_scroll = if back {
+            (self.chat_scroll + GameConstants::CHAT_SCROLL_STEP).min(oldest)
+        } else {
+            self.chat_scroll.saturating_sub(GameConstants::CHAT_SCROLL_STEP)
+        };
+    }
+
     pub fn open_chat(&mut self) {
         if self.game_mode == GameMode::MultiPlayer {
             self.chat_input_mode = true;
@@ -580,7 +1386,8 @@ impl App {
     }
 
     pub fn add_char_to_chat(&mut self, c: char) {
-        if self.chat_input.len() < 100 { // Limit chat message length
+        // Newlines and escape codes would break the chat line for everyone
+        if !c.is_control() && self.chat_input.chars().count() < GameConstants::MAX_CHAT_LENGTH {
             self.chat_input.push(c);
         }
     }
@@ -590,10 +1397,10 @@ impl App {
     }
 
     pub fn disconnect(&mut self) {
-        if let Some(ref client) = self.network_client {
+        if let Some(client) = self.network_client.take() {
             client.disconnect();
         }
-        self.network_client = None;
+        self.clear_chat();
         self.current_screen = CurrentScreen::MainMenu;
         self.main_menu_state = MainMenuState::new();
     }
@@ -653,10 +1460,11 @@ impl App {
             let player_chunk_y = if self.player.y >= 0 { self.player.y / 32 } else { (self.player.y - 31) / 32 };
             
             let mut chunks_to_request = Vec::new();
+            let view_distance = self.config.view_distance;
             
-            // Request 3x3 grid of chunks around player
-            for dx in -1..=1 {
-                for dy in -1..=1 {
+            // Request the square of chunks within view distance of the player
+            for dx in -view_distance..=view_distance {
+                for dy in -view_distance..=view_distance {
                     let chunk_x = player_chunk_x + dx;
                     let chunk_y = player_chunk_y + dy;
                     
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use rust_cli_roguelike::common::constants::{Difficulty, GameConstants};
+use rust_cli_roguelike::common::chunk::{BiomeConfig, ChunkSettings};
+use rust_cli_roguelike::common::game_logic::GameLogic;
+
+use crate::notify::NotifyEvent;
+
+/// Client settings, read from the config file if it exists and then
+/// overridden by command line flags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    pub bell: bool, // Ring the terminal bell on important events
+    pub bell_events: Vec<NotifyEvent>, // Which events ring it; only set in the config file
+    pub zoom: TileZoom, // Initial map zoom, can be cycled in game
+    pub chunks: ChunkSettings, // How many chunks stay loaded in single player
+    pub biomes: BiomeConfig, // Terrain thresholds for single player worlds; only set in the config file
+    pub view_distance: i32, // Radius of chunks requested from the server in multiplayer
+    pub spawn: Option<(i32, i32)>, // Base single player spawn, moved to the nearest walkable tile
+    pub room: Option<String>, // Multiplayer room code, the server's default world if unset
+    pub dungeon_prompt: bool, // Ask to enter when stepping onto a dungeon entrance
+    pub compass: bool, // Show a compass with the player's coordinates on the overworld
+    pub difficulty: Difficulty, // Preset preselected in the main menu for single player
+    pub chat_history: usize, // Chat messages kept to scroll back through
+    pub fps: u32, // Most frames drawn per second; the screen is only redrawn when something changed
+    pub connect_timeout_secs: u64, // Per connection attempt
+    pub connect_retries: u32, // Extra attempts, with a growing delay, before giving up
+    pub autosave_secs: u64, // Seconds between single player world saves, 0 to only save on quit
+    pub debug: bool, // Enable testing aids such as revealing the whole dungeon
+    pub dungeon_seed: Option<u32>, // Every single player dungeon uses this layout, whichever entrance it's entered from
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            bell: false,
+            bell_events: vec![NotifyEvent::Damage, NotifyEvent::PlayerJoined, NotifyEvent::ChatMention],
+            zoom: TileZoom::default(),
+            chunks: ChunkSettings::default(),
+            biomes: BiomeConfig::default(),
+            view_distance: 1,
+            spawn: None,
+            room: None,
+            dungeon_prompt: true,
+            compass: false,
+            difficulty: Difficulty::default(),
+            chat_history: GameConstants::CHAT_HISTORY,
+            fps: 20,
+            connect_timeout_secs: GameConstants::CONNECT_TIMEOUT_SECS,
+            connect_retries: 0,
+            autosave_secs: GameConstants::AUTOSAVE_SECS,
+            debug: false,
+            dungeon_seed: None,
+        }
+    }
+}
+
+/// How many terminal cells each map tile is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TileZoom {
+    #[default]
+    Normal, // 1x1
+    Wide,   // 2x1, roughly square tiles on most fonts
+    Large,  // 2x2
+}
+
+impl TileZoom {
+    /// Terminal cells (columns, rows) used to draw a single tile
+    pub fn cell_size(self) -> (i32, i32) {
+        match self {
+            TileZoom::Normal => (1, 1),
+            TileZoom::Wide => (2, 1),
+            TileZoom::Large => (2, 2),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            TileZoom::Normal => TileZoom::Wide,
+            TileZoom::Wide => TileZoom::Large,
+            TileZoom::Large => TileZoom::Normal,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TileZoom::Normal => "Normal (1x1)",
+            TileZoom::Wide => "Wide (2x1)",
+            TileZoom::Large => "Large (2x2)",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "normal" => Some(TileZoom::Normal),
+            "wide" => Some(TileZoom::Wide),
+            "large" => Some(TileZoom::Large),
+            _ => None,
+        }
+    }
+}
+
+impl ClientConfig {
+    pub fn rings_bell_for(&self, event: NotifyEvent) -> bool {
+        self.bell && self.bell_events.contains(&event)
+    }
+
+    /// Load the config for this run from the config file and process arguments
+    pub fn load() -> Result<Self, String> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+
+        // An explicit --config path must exist; the default path is optional
+        let explicit_path = args.iter()
+            .position(|arg| arg == "--config")
+            .map(|i| args.get(i + 1).cloned().ok_or("--config needs a file path"))
+            .transpose()?;
+
+        let mut config = match explicit_path {
+            Some(path) => Self::from_file(&path)?,
+            None if std::path::Path::new(GameConstants::CLIENT_CONFIG_PATH).exists() => {
+                Self::from_file(GameConstants::CLIENT_CONFIG_PATH)?
+            }
+            None => Self::default(),
+        };
+
+        config.apply_args(&args)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path, e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid config file {}: {}", path, e))
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        if self.room.is_some() {
+            self.room = Some(GameLogic::normalize_room_code(self.room.as_deref())?);
+        }
+        self.chunks.validate()?;
+        self.biomes.validate()?;
+        if self.view_distance < 1 || self.view_distance > self.chunks.load_radius {
+            return Err(format!(
+                "View distance must be between 1 and the chunk load radius ({})",
+                self.chunks.load_radius
+            ));
+        }
+        if !(1..=GameConstants::MAX_FPS).contains(&self.fps) {
+            return Err(format!("FPS must be between 1 and {}", GameConstants::MAX_FPS));
+        }
+        if self.connect_timeout_secs == 0 {
+            return Err("Connect timeout must be at least 1 second".to_string());
+        }
+        if self.chat_history == 0 {
+            return Err("Chat history must keep at least 1 message".to_string());
+        }
+        Ok(())
+    }
+
+    /// Apply command line overrides on top of the file settings
+    fn apply_args(&mut self, args: &[String]) -> Result<(), String> {
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    args.next(); // Already handled in load()
+                }
+                "--bell" => self.bell = true,
+                "--no-bell" => self.bell = false,
+                "--dungeon-prompt" => self.dungeon_prompt = true,
+                "--no-dungeon-prompt" => self.dungeon_prompt = false,
+                "--compass" => self.compass = true,
+                "--no-compass" => self.compass = false,
+                "--debug" => self.debug = true,
+                "--zoom" => {
+                    let value = args.next().ok_or("--zoom needs normal, wide or large")?;
+                    self.zoom = TileZoom::parse(value)
+                        .ok_or_else(|| format!("Unknown zoom level: {}", value))?;
+                }
+                "--difficulty" => {
+                    let value = args.next().ok_or("--difficulty needs easy, normal or hard")?;
+                    self.difficulty = Difficulty::parse(value)
+                        .ok_or_else(|| format!("Unknown difficulty: {}", value))?;
+                }
+                "--chunk-radius" => self.chunks.load_radius = parse_number(arg, args.next().map(String::as_str))?,
+                "--max-chunks" => self.chunks.max_loaded_chunks = parse_number(arg, args.next().map(String::as_str))?,
+                "--chunk-timeout" => self.chunks.unload_timeout_secs = parse_number(arg, args.next().map(String::as_str))?,
+                "--view-distance" => self.view_distance = parse_number(arg, args.next().map(String::as_str))?,
+                "--chat-history" => self.chat_history = parse_number(arg, args.next().map(String::as_str))?,
+                "--fps" => self.fps = parse_number(arg, args.next().map(String::as_str))?,
+                "--connect-timeout" => self.connect_timeout_secs = parse_number(arg, args.next().map(String::as_str))?,
+                "--dungeon-seed" => self.dungeon_seed = Some(parse_number(arg, args.next().map(String::as_str))?),
+                "--autosave" => self.autosave_secs = parse_number(arg, args.next().map(String::as_str))?,
+                "--no-autosave" => self.autosave_secs = 0,
+                "--connect-retries" => self.connect_retries = parse_number(arg, args.next().map(String::as_str))?,
+                "--room" => {
+                    self.room = Some(args.next().ok_or("--room needs a room code")?.clone());
+                }
+                "--spawn" => {
+                    let value = args.next().ok_or("--spawn needs a position like 30,15")?;
+                    let (x, y) = value.split_once(',').ok_or_else(|| format!("Invalid spawn position: {}", value))?;
+                    self.spawn = Some((parse_number(arg, Some(x.trim()))?, parse_number(arg, Some(y.trim()))?));
+                }
+                other => return Err(format!("Unknown argument: {}", other)),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<&str>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a number", flag))?;
+    value.parse().map_err(|_| format!("Invalid number for {}: {}", flag, value))
+}
@@ -0,0 +1,82 @@
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use rust_cli_roguelike::common::constants::GameConstants;
+use rust_cli_roguelike::common::protocol::LanAnnouncement;
+use socket2::{Domain, Protocol, Socket, Type};
+
+/// A server heard announcing itself on the local network
+#[derive(Debug, Clone)]
+pub struct LanServer {
+    pub address: SocketAddr,
+    pub players: usize,
+    last_seen: Instant,
+}
+
+/// Listens for LAN server announcements while the menu's server list is open
+pub struct LanBrowser {
+    socket: UdpSocket,
+    pub servers: Vec<LanServer>,
+    pub selected: usize,
+}
+
+impl LanBrowser {
+    pub fn open() -> io::Result<Self> {
+        // Shared with other clients on this machine; every one of them gets each broadcast
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_reuse_address(true)?;
+        #[cfg(unix)]
+        socket.set_reuse_port(true)?;
+        socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, GameConstants::LAN_DISCOVERY_PORT)).into())?;
+        socket.set_nonblocking(true)?;
+        Ok(LanBrowser { socket: socket.into(), servers: Vec::new(), selected: 0 })
+    }
+
+    /// Take in any announcements that arrived and forget servers that went quiet.
+    /// Returns whether the list changed.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        let mut buffer = [0u8; 512];
+        while let Ok((len, from)) = self.socket.recv_from(&mut buffer) {
+            let Ok(announcement) = serde_json::from_slice::<LanAnnouncement>(&buffer[..len]) else {
+                continue; // Not one of ours
+            };
+            let address = SocketAddr::new(from.ip(), announcement.port);
+            match self.servers.iter_mut().find(|server| server.address == address) {
+                Some(server) => {
+                    changed |= server.players != announcement.players;
+                    server.players = announcement.players;
+                    server.last_seen = Instant::now();
+                }
+                None => {
+                    self.servers.push(LanServer { address, players: announcement.players, last_seen: Instant::now() });
+                    changed = true;
+                }
+            }
+        }
+
+        let timeout = Duration::from_secs(GameConstants::LAN_SERVER_TIMEOUT_SECS);
+        let before = self.servers.len();
+        self.servers.retain(|server| server.last_seen.elapsed() < timeout);
+        changed |= self.servers.len() != before;
+        self.selected = self.selected.min(self.servers.len().saturating_sub(1));
+        changed
+    }
+
+    pub fn selected_server(&self) -> Option<&LanServer> {
+        self.servers.get(self.selected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn several_clients_can_browse_at_once() {
+        let first = LanBrowser::open().expect("first browser");
+        let second = LanBrowser::open().expect("a second browser on the same machine");
+        drop((first, second));
+    }
+}
@@ -1,10 +1,16 @@
 // ANCHOR: all
-use std::{error::Error, io};
+use std::{
+    error::Error,
+    io,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
-        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+        cursor,
+        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers},
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
@@ -12,12 +18,19 @@ use ratatui::{
 };
 
 mod app;
+mod config;
+mod notify;
 mod ui;
 mod network;
+mod lan;
+#[cfg(unix)]
+mod suspend;
 
-use rust_cli_roguelike::common::protocol;
+use rust_cli_roguelike::common::constants::GameConstants;
+use rust_cli_roguelike::common::protocol::{self, TileModification};
 use crate::{
-    app::{App, CurrentScreen, GameMode, NetworkClient},
+    app::{App, CurrentScreen, GameMode, SessionSummary},
+    config::ClientConfig,
     ui::ui,
 };
 
@@ -25,17 +38,21 @@ use crate::{
 // ANCHOR: setup_boilerplate
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    // Read settings before touching the terminal so errors print normally
+    let config = ClientConfig::load()?;
+
     // setup terminal
     enable_raw_mode()?;
     let mut stderr = io::stderr(); // This is a special case. Normally using stdout is fine
     execute!(stderr, EnterAlternateScreen, EnableMouseCapture)?;
+    restore_terminal_on_panic();
     // ANCHOR_END: setup_boilerplate
     // ANCHOR: application_startup
     let backend = CrosstermBackend::new(stderr);
     let mut terminal = Terminal::new(backend)?;
 
     // create app and run it
-    let app = App::new();
+    let app = App::new(config);
     let res = run_app(&mut terminal, app).await;
     // ANCHOR_END: application_startup
 
@@ -48,11 +65,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
         DisableMouseCapture
     )?;
     terminal.show_cursor()?;
+    for panic in TASK_PANICS.lock().map(|panics| panics.clone()).unwrap_or_default() {
+        eprintln!("Background task {}", panic);
+    }
     // ANCHOR_END: ending_boilerplate
 
     // ANCHOR: final_print
-    if let Err(err) = res {
-        println!("{err:?}");
+    match res {
+        // Only worth a recap if a game was actually played
+        Ok(summary) if summary.turns_taken > 0 => println!("{summary}"),
+        Ok(_) => {}
+        Err(err) => eprintln!("{err:?}"),
     }
 
     Ok(())
@@ -62,204 +85,803 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
 // ANCHOR: run_app_all
 // ANCHOR: run_method_signature
-async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<SessionSummary> {
+    let frame_interval = Duration::from_millis(1000 / app.config.fps as u64);
+    let network_interval = Duration::from_millis(GameConstants::NETWORK_POLL_INTERVAL_MS);
+    let heartbeat = Duration::from_millis(GameConstants::REDRAW_HEARTBEAT_MS);
+    let mut last_draw: Option<Instant> = None;
+    let mut last_network_poll = Instant::now();
+    let mut needs_redraw = true;
+    #[cfg(unix)]
+    let suspend_signal = suspend::SuspendSignal::register()?;
+
     loop {
-        // Process network messages if in multiplayer mode
-        if app.game_mode == GameMode::MultiPlayer {
-            app.process_network_messages();
+        #[cfg(unix)]
+        if suspend_signal.take() {
+            suspend::suspend(terminal)?;
+            needs_redraw = true;
         }
 
-        terminal.draw(|f| ui(f, &mut app))?;
-
-        // Use a timeout for event reading so we can process network messages more frequently
-        let timeout = std::time::Duration::from_millis(50); // 20 FPS
-        if let Ok(has_event) = event::poll(timeout) {
-            if has_event {
-                if let Event::Key(key) = event::read()? {
-                    if key.kind == ratatui::crossterm::event::KeyEventKind::Press {
-                        match app.current_screen {
-                            CurrentScreen::MainMenu => {
-                                if app.main_menu_state.username_input_mode {
-                                    // Handle username input
-                                    match key.code {
-                                        KeyCode::Enter => {
-                                            app.finish_username_input();
-                                        }
-                                        KeyCode::Esc => {
-                                            app.cancel_username_input();
-                                        }
-                                        KeyCode::Backspace => {
-                                            app.remove_char_from_username();
-                                        }
-                                        KeyCode::Char(c) => {
-                                            app.add_char_to_username(c);
-                                        }
-                                        _ => {}
+        if app.poll_connection().await {
+            needs_redraw = true;
+        }
+        // Keep the connecting spinner moving
+        if app.main_menu_state.connecting.is_some() {
+            needs_redraw = true;
+        }
+        needs_redraw |= app.autosave_if_due();
+        needs_redraw |= app.animate_map_transition();
+        if let Some(browser) = app.lan_browser.as_mut() {
+            needs_redraw |= browser.poll();
+        }
+
+        // Check the server on its own cadence, independent of the frame rate
+        let multiplayer = app.game_mode == GameMode::MultiPlayer;
+        if multiplayer && last_network_poll.elapsed() >= network_interval {
+            needs_redraw |= app.process_network_messages();
+            last_network_poll = Instant::now();
+        }
+
+        // A slow heartbeat keeps anything that changes with time (e.g. animations) fresh
+        if last_draw.is_some_and(|drawn| drawn.elapsed() >= heartbeat) {
+            needs_redraw = true;
+        }
+
+        // Only redraw when something changed, and at most once per frame
+        if needs_redraw && last_draw.is_none_or(|drawn| drawn.elapsed() >= frame_interval) {
+            terminal.draw(|f| ui(f, &mut app))?;
+            last_draw = Some(Instant::now());
+            needs_redraw = false;
+        }
+
+        // Sleep until input arrives or a frame, heartbeat or network poll is due,
+        // so an idle client barely wakes up
+        let since_draw = last_draw.map_or(Duration::ZERO, |drawn| drawn.elapsed());
+        let mut timeout = heartbeat.saturating_sub(since_draw);
+        if needs_redraw {
+            timeout = timeout.min(frame_interval.saturating_sub(since_draw));
+        }
+        if multiplayer || app.lan_browser.is_some() {
+            timeout = timeout.min(network_interval.saturating_sub(last_network_poll.elapsed()));
+        }
+
+        let drained = drain_events(&mut app, &mut TerminalEvents, timeout)?;
+        needs_redraw |= drained.handled; // Key presses and resizes both change the screen
+        #[cfg(unix)]
+        if drained.suspend {
+            suspend::suspend(terminal)?;
+            needs_redraw = true;
+        }
+
+        if app.should_quit {
+            // Single player progress is only saved on request (save and quit)
+            if app.game_mode == GameMode::MultiPlayer {
+                app.disconnect();
+            }
+            break;
+        }
+    }
+    Ok(app.session)
+}
+// ANCHOR_END: run_app_all
+
+/// Where input comes from; the terminal, or a scripted queue in tests
+trait EventSource {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+    fn read(&mut self) -> io::Result<Event>;
+}
+
+struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+}
+
+/// What a pass over the queued input did
+#[derive(Debug, Default, PartialEq)]
+struct Drained {
+    handled: bool, // At least one event reached the app
+    #[cfg_attr(not(unix), allow(dead_code))] // Ctrl+Z only suspends on unix
+    suspend: bool, // Stopped at Ctrl+Z, which the caller has to act on
+}
+
+/// Wait up to `timeout` for input, then handle everything already queued before
+/// the next draw so keys pressed during a slow frame aren't left behind
+fn drain_events(app: &mut App, events: &mut impl EventSource, timeout: Duration) -> io::Result<Drained> {
+    let mut drained = Drained::default();
+    let mut wait = timeout;
+    while let Ok(true) = events.poll(wait) {
+        wait = Duration::ZERO;
+        let event = events.read()?;
+        #[cfg(unix)]
+        if suspend::is_suspend_key(&event) {
+            drained.suspend = true;
+            break;
+        }
+        handle_event(app, event);
+        drained.handled = true;
+        if app.should_quit {
+            break;
+        }
+    }
+    Ok(drained)
+}
+
+/// Panics in background tasks, printed once the terminal is back to normal
+static TASK_PANICS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// A panic skips the cleanup at the end of `main`, so put the terminal back
+/// first or the panic message lands in a raw-mode alternate screen.
+/// Only panics on the main thread end the game; a panicking tokio task (say a
+/// connection) is reported by whoever awaits it while the game carries on, so
+/// its message is kept for after exit instead of scribbled over the screen.
+fn restore_terminal_on_panic() {
+    let main_thread = std::thread::current().id();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().id() != main_thread {
+            if let Ok(mut panics) = TASK_PANICS.lock() {
+                panics.push(info.to_string());
+            }
+            return;
+        }
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stderr(), LeaveAlternateScreen, DisableMouseCapture, cursor::Show);
+        default_hook(info);
+    }));
+}
+
+/// Apply one terminal event to the app
+fn handle_event(app: &mut App, event: Event) {
+    if let Event::Key(key) = event {
+        if key.kind == ratatui::crossterm::event::KeyEventKind::Press {
+            match app.current_screen {
+                CurrentScreen::MainMenu => {
+                    if app.main_menu_state.username_input_mode {
+                        // Handle username input
+                        match key.code {
+                            KeyCode::Enter => {
+                                app.finish_username_input();
+                            }
+                            KeyCode::Esc => {
+                                app.cancel_username_input();
+                            }
+                            KeyCode::Backspace => {
+                                app.remove_char_from_username();
+                            }
+                            KeyCode::Char(c) => {
+                                app.add_char_to_username(c);
+                            }
+                            _ => {}
+                        }
+                    } else if let Some(browser) = app.lan_browser.as_mut() {
+                        // Pick a server from the LAN list
+                        match key.code {
+                            KeyCode::Up => {
+                                browser.selected = browser.selected.saturating_sub(1);
+                            }
+                            KeyCode::Down if browser.selected + 1 < browser.servers.len() => {
+                                browser.selected += 1;
+                            }
+                            KeyCode::Enter => {
+                                app.join_selected_lan_server();
+                            }
+                            KeyCode::Esc => {
+                                app.close_lan_browser();
+                            }
+                            _ => {}
+                        }
+                    } else {
+                        // Handle menu navigation
+                        match key.code {
+                            KeyCode::Up if app.main_menu_state.selected_option > 0 => {
+                                app.main_menu_state.selected_option -= 1;
+                            }
+                            KeyCode::Down if app.main_menu_state.selected_option < 6 => { // Updated for 7 options
+                                app.main_menu_state.selected_option += 1;
+                            }
+                            KeyCode::Esc => {
+                                app.cancel_connecting();
+                            }
+                            // Wait for a running connection attempt before starting anything else
+                            KeyCode::Enter if app.main_menu_state.connecting.is_none() => {
+                                match app.main_menu_state.selected_option {
+                                    0 => {
+                                        // Single Player
+                                        app.start_single_player();
                                     }
-                                } else {
-                                    // Handle menu navigation
-                                    match key.code {
-                                        KeyCode::Up => {
-                                            if app.main_menu_state.selected_option > 0 {
-                                                app.main_menu_state.selected_option -= 1;
-                                            }
-                                        }
-                                        KeyCode::Down => {
-                                            if app.main_menu_state.selected_option < 3 { // Updated for 4 options
-                                                app.main_menu_state.selected_option += 1;
-                                            }
-                                        }
-                                        KeyCode::Enter => {
-                                            match app.main_menu_state.selected_option {
-                                                0 => {
-                                                    // Single Player
-                                                    app.start_single_player();
-                                                }
-                                                1 => {
-                                                    // Multiplayer - try to connect
-                                                    app.main_menu_state.connecting = true;
-                                                    match NetworkClient::connect(&app.server_address, app.player_name.clone()).await {
-                                                        Ok(client) => {
-                                                            app.start_multiplayer(client);
-                                                        }
-                                                        Err(e) => {
-                                                            app.main_menu_state.connecting = false;
-                                                            app.main_menu_state.connection_error = Some(format!("Failed to connect: {}", e));
-                                                        }
-                                                    }
-                                                }
-                                                2 => {
-                                                    // Set Username
-                                                    app.start_username_input();
-                                                }
-                                                3 => {
-                                                    // Quit
-                                                    app.should_quit = true;
-                                                }
-                                                _ => {}
-                                            }
-                                        }
-                                        KeyCode::Char('q') => {
-                                            app.should_quit = true;
-                                        }
-                                        _ => {}
+                                    1 => {
+                                        // Survival (single player with hunger)
+                                        app.start_survival();
                                     }
-                                }
-                            },
-                            CurrentScreen::Game => {
-                                if app.chat_input_mode {
-                                    // Handle chat input mode
-                                    match key.code {
-                                        KeyCode::Enter => {
-                                            app.send_chat_message();
-                                        }
-                                        KeyCode::Esc => {
-                                            app.close_chat();
-                                        }
-                                        KeyCode::Backspace => {
-                                            app.remove_char_from_chat();
-                                        }
-                                        KeyCode::Char(c) => {
-                                            app.add_char_to_chat(c);
-                                        }
-                                        _ => {}
+                                    2 => {
+                                        // Difficulty for the single player modes
+                                        app.cycle_difficulty();
                                     }
-                                } else {
-                                    // Handle normal game controls
-                                    match key.code {
-                                        KeyCode::Char('q') => {
-                                            if app.game_mode == GameMode::MultiPlayer {
-                                                app.disconnect();
-                                            } else {
-                                                app.current_screen = CurrentScreen::Exiting;
-                                            }
-                                        }
-                                        KeyCode::Char('i') => {
-                                            app.open_inventory();
-                                        }
-                                        KeyCode::Char('c') => {
-                                            app.open_chat();
-                                        }
-                                        KeyCode::Char('e') => {
-                                            app.enter_dungeon();
-                                        }
-                                        KeyCode::Char('x') => {
-                                            app.exit_dungeon();
-                                        }
-                                        // Movement keys (vi-style)
-                                        KeyCode::Char('h') | KeyCode::Left => {
-                                            app.move_player(-1, 0);
-                                        }
-                                        KeyCode::Char('j') | KeyCode::Down => {
-                                            app.move_player(0, 1);
-                                        }
-                                        KeyCode::Char('k') | KeyCode::Up => {
-                                            app.move_player(0, -1);
-                                        }
-                                        KeyCode::Char('l') | KeyCode::Right => {
-                                            app.move_player(1, 0);
-                                        }
-                                        // Diagonal movement
-                                        KeyCode::Char('y') => app.move_player(-1, -1),
-                                        KeyCode::Char('u') => app.move_player(1, -1),
-                                        KeyCode::Char('b') => app.move_player(-1, 1),
-                                        KeyCode::Char('n') => app.move_player(1, 1),
-                                        _ => {}
+                                    3 => {
+                                        // Multiplayer - connect in the background
+                                        app.start_connecting();
                                     }
-                                }
-                            },
-                            CurrentScreen::Inventory => match key.code {
-                                KeyCode::Char('g') | KeyCode::Esc => {
-                                    app.close_inventory();
-                                }
-                                KeyCode::Char('q') => {
-                                    if app.game_mode == GameMode::MultiPlayer {
-                                        app.disconnect();
-                                    } else {
-                                        app.current_screen = CurrentScreen::Exiting;
+                                    4 => {
+                                        // LAN Games - list servers announcing themselves nearby
+                                        app.open_lan_browser();
                                     }
+                                    5 => {
+                                        // Set Username
+                                        app.start_username_input();
+                                    }
+                                    6 => {
+                                        // Quit
+                                        app.should_quit = true;
+                                    }
+                                    _ => {}
                                 }
-                                _ => {}
-                            },
-                            CurrentScreen::Chat => match key.code {
-                                KeyCode::Enter => {
-                                    app.send_chat_message();
-                                }
-                                KeyCode::Esc => {
-                                    app.close_chat();
-                                }
-                                KeyCode::Backspace => {
-                                    app.remove_char_from_chat();
-                                }
-                                KeyCode::Char(c) => {
-                                    app.add_char_to_chat(c);
-                                }
-                                _ => {}
-                            },
-                            CurrentScreen::Exiting => match key.code {
-                                KeyCode::Char('y') => {
-                                    app.should_quit = true;
-                                }
-                                KeyCode::Char('n') | KeyCode::Esc => {
-                                    app.current_screen = CurrentScreen::Game;
-                                }
-                                _ => {}
-                            },
+                            }
+                            KeyCode::Char('q') => {
+                                app.should_quit = true;
+                            }
+                            _ => {}
+                        }
+                    }
+                },
+                CurrentScreen::Game => {
+                    if app.chat_input_mode {
+                        // Handle chat input mode
+                        match key.code {
+                            KeyCode::PageUp => app.scroll_chat(true),
+                            KeyCode::PageDown => app.scroll_chat(false),
+                            KeyCode::Enter => {
+                                app.send_chat_message();
+                            }
+                            KeyCode::Esc => {
+                                app.close_chat();
+                            }
+                            KeyCode::Backspace => {
+                                app.remove_char_from_chat();
+                            }
+                            KeyCode::Char(c) => {
+                                app.add_char_to_chat(c);
+                            }
+                            _ => {}
+                        }
+                    } else if app.dungeon_prompt_open
+                        && matches!(key.code, KeyCode::Char('y') | KeyCode::Char('n') | KeyCode::Esc)
+                    {
+                        app.answer_dungeon_prompt(key.code == KeyCode::Char('y'));
+                    } else {
+                        // Any other key dismisses the dungeon prompt and acts as usual
+                        app.dungeon_prompt_open = false;
+
+                        if let Some(action) = game_action(&key) {
+                            perform_game_action(app, action);
+                        }
+                    }
+                },
+                CurrentScreen::Inventory => match key.code {
+                    KeyCode::Char('g') | KeyCode::Esc => {
+                        app.close_inventory();
+                    }
+                    KeyCode::Char('e') => {
+                        app.eat_food();
+                    }
+                    KeyCode::Char('r') => {
+                        app.read_recall_scroll();
+                    }
+                    KeyCode::Char('s') => {
+                        app.scout_dungeon();
+                    }
+                    KeyCode::Char('q') => {
+                        if app.game_mode == GameMode::MultiPlayer {
+                            app.disconnect();
+                        } else {
+                            app.current_screen = CurrentScreen::Exiting;
                         }
                     }
-                }
+                    _ => {}
+                },
+                CurrentScreen::FastTravel => match key.code {
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.select_previous_village();
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.select_next_village();
+                    }
+                    KeyCode::Enter => {
+                        app.fast_travel_to_selected();
+                    }
+                    KeyCode::Char('t') | KeyCode::Esc => {
+                        app.close_fast_travel();
+                    }
+                    _ => {}
+                },
+                CurrentScreen::Help => match key.code {
+                    KeyCode::Char('?') | KeyCode::Esc => {
+                        app.current_screen = CurrentScreen::Game;
+                    }
+                    _ => {}
+                },
+                CurrentScreen::Chat => match key.code {
+                    KeyCode::PageUp => app.scroll_chat(true),
+                    KeyCode::PageDown => app.scroll_chat(false),
+                    KeyCode::Enter => {
+                        app.send_chat_message();
+                    }
+                    KeyCode::Esc => {
+                        app.close_chat();
+                    }
+                    KeyCode::Backspace => {
+                        app.remove_char_from_chat();
+                    }
+                    KeyCode::Char(c) => {
+                        app.add_char_to_chat(c);
+                    }
+                    _ => {}
+                },
+                CurrentScreen::Exiting => match key.code {
+                    KeyCode::Char('y') => {
+                        app.should_quit = true;
+                    }
+                    KeyCode::Char('s') if app.has_unsaved_changes() => {
+                        app.save_and_quit();
+                    }
+                    KeyCode::Char('n') | KeyCode::Esc => {
+                        app.current_screen = CurrentScreen::Game;
+                    }
+                    _ => {}
+                },
             }
         }
+    }
+}
 
-        if app.should_quit {
+// ANCHOR_END: all
+
+/// What a key does on the game screen. Kept apart from performing it so the
+/// help screen can be checked against every bound key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GameAction {
+    Quit,
+    OpenInventory,
+    OpenChat,
+    EnterDungeon,
+    ExitDungeon,
+    CycleZoom,
+    ToggleMinimap,
+    CycleMessageFilter,
+    ToggleNameTags,
+    RevealDungeon,
+    ToggleCoordinateGrid,
+    OpenFastTravel,
+    OpenHelp,
+    Wait,
+    ScrollChat { back: bool },
+    ModifyTile(TileModification),
+    Sprint(i32, i32),
+    Move(i32, i32),
+}
+
+fn game_action(key: &KeyEvent) -> Option<GameAction> {
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+    let action = match key.code {
+        KeyCode::Char('q') => GameAction::Quit,
+        KeyCode::Char('i') => GameAction::OpenInventory,
+        KeyCode::Char('c') => GameAction::OpenChat,
+        KeyCode::Char('e') => GameAction::EnterDungeon,
+        KeyCode::Char('x') => GameAction::ExitDungeon,
+        KeyCode::Char('z') => GameAction::CycleZoom,
+        KeyCode::Char('m') => GameAction::ToggleMinimap,
+        KeyCode::Char('o') => GameAction::CycleMessageFilter,
+        KeyCode::Char('p') => GameAction::ToggleNameTags,
+        KeyCode::Char('V') => GameAction::RevealDungeon,
+        KeyCode::Char('G') => GameAction::ToggleCoordinateGrid,
+        KeyCode::Char('t') => GameAction::OpenFastTravel,
+        KeyCode::Char('?') => GameAction::OpenHelp,
+        KeyCode::Char('.') => GameAction::Wait,
+        KeyCode::PageUp => GameAction::ScrollChat { back: true },
+        KeyCode::PageDown => GameAction::ScrollChat { back: false },
+        KeyCode::Char('f') => GameAction::ModifyTile(TileModification::ChopTree),
+        KeyCode::Char('r') => GameAction::ModifyTile(TileModification::LayRoad),
+        // Shift + direction sprints until something stops it
+        KeyCode::Char('H') => GameAction::Sprint(-1, 0),
+        KeyCode::Char('J') => GameAction::Sprint(0, 1),
+        KeyCode::Char('K') => GameAction::Sprint(0, -1),
+        KeyCode::Char('L') => GameAction::Sprint(1, 0),
+        KeyCode::Char('Y') => GameAction::Sprint(-1, -1),
+        KeyCode::Char('U') => GameAction::Sprint(1, -1),
+        KeyCode::Char('B') => GameAction::Sprint(-1, 1),
+        KeyCode::Char('N') => GameAction::Sprint(1, 1),
+        KeyCode::Left if shift => GameAction::Sprint(-1, 0),
+        KeyCode::Down if shift => GameAction::Sprint(0, 1),
+        KeyCode::Up if shift => GameAction::Sprint(0, -1),
+        KeyCode::Right if shift => GameAction::Sprint(1, 0),
+        // Movement keys (vi-style)
+        KeyCode::Char('h') | KeyCode::Left => GameAction::Move(-1, 0),
+        KeyCode::Char('j') | KeyCode::Down => GameAction::Move(0, 1),
+        KeyCode::Char('k') | KeyCode::Up => GameAction::Move(0, -1),
+        KeyCode::Char('l') | KeyCode::Right => GameAction::Move(1, 0),
+        // Diagonal movement
+        KeyCode::Char('y') => GameAction::Move(-1, -1),
+        KeyCode::Char('u') => GameAction::Move(1, -1),
+        KeyCode::Char('b') => GameAction::Move(-1, 1),
+        KeyCode::Char('n') => GameAction::Move(1, 1),
+        _ => return None,
+    };
+    Some(action)
+}
+
+fn perform_game_action(app: &mut App, action: GameAction) {
+    match action {
+        GameAction::Quit => {
             if app.game_mode == GameMode::MultiPlayer {
                 app.disconnect();
+            } else {
+                app.current_screen = CurrentScreen::Exiting;
             }
-            break;
         }
+        GameAction::OpenInventory => app.open_inventory(),
+        GameAction::OpenChat => app.open_chat(),
+        GameAction::EnterDungeon => app.enter_dungeon(),
+        GameAction::ExitDungeon => app.exit_dungeon(),
+        GameAction::CycleZoom => app.cycle_zoom(),
+        GameAction::ToggleMinimap => app.toggle_dungeon_minimap(),
+        GameAction::CycleMessageFilter => app.cycle_message_filter(),
+        GameAction::ToggleNameTags => app.toggle_name_tags(),
+        GameAction::RevealDungeon => app.reveal_whole_dungeon(),
+        GameAction::ToggleCoordinateGrid => app.toggle_coordinate_grid(),
+        GameAction::OpenFastTravel => app.open_fast_travel(),
+        GameAction::OpenHelp => app.current_screen = CurrentScreen::Help,
+        GameAction::Wait => app.wait_turn(),
+        GameAction::ScrollChat { back } => app.scroll_chat(back),
+        GameAction::ModifyTile(modification) => app.modify_tile(modification),
+        GameAction::Sprint(dx, dy) => app.sprint(dx, dy),
+        GameAction::Move(dx, dy) => app.move_player(dx, dy),
     }
-    Ok(())
 }
-// ANCHOR_END: run_app_all
 
-// ANCHOR_END: all
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{GameMap, MapType, Tile};
+
+    #[test]
+    fn the_help_lists_exactly_the_bound_game_keys() {
+        // Letters carry their case, so only the other keys need Shift tried
+        let keys = (' '..='~')
+            .map(|c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+            .chain([
+                KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down, KeyCode::PageUp, KeyCode::PageDown,
+                KeyCode::Home, KeyCode::End, KeyCode::Enter, KeyCode::Esc, KeyCode::Tab, KeyCode::Backspace,
+            ].into_iter().flat_map(|code| [KeyModifiers::NONE, KeyModifiers::SHIFT].map(|modifiers| KeyEvent::new(code, modifiers))));
+
+        for key in keys {
+            let listed = ui::CONTROLS.iter()
+                .flat_map(|(_, _, bound)| bound.iter())
+                .any(|listed| listed.code == key.code && listed.modifiers == key.modifiers);
+            // Shift that changes nothing (Shift+PageUp) is covered by the plain key's row
+            let unshifted = KeyEvent::new(key.code, KeyModifiers::NONE);
+            let same_as_unshifted = key.modifiers == KeyModifiers::SHIFT && game_action(&key).is_some() && game_action(&key) == game_action(&unshifted);
+            assert_eq!(game_action(&key).is_some(), listed || same_as_unshifted, "{:?} with {:?}", key.code, key.modifiers);
+        }
+    }
+
+    /// Input queued up in advance, remembering how long each poll was allowed to wait
+    struct ScriptedEvents {
+        queue: std::collections::VecDeque<Event>,
+        waits: Vec<Duration>,
+    }
+
+    impl EventSource for ScriptedEvents {
+        fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+            self.waits.push(timeout);
+            Ok(!self.queue.is_empty())
+        }
+
+        fn read(&mut self) -> io::Result<Event> {
+            Ok(self.queue.pop_front().expect("read without a pending event"))
+        }
+    }
+
+    #[test]
+    fn a_burst_of_queued_keys_is_handled_in_full() {
+        let mut app = App::new(ClientConfig::default());
+        let down = Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        // Everything that piled up during one slow frame
+        let mut events = ScriptedEvents { queue: vec![down; 4].into(), waits: Vec::new() };
+
+        let drained = drain_events(&mut app, &mut events, Duration::from_millis(50)).unwrap();
+        assert_eq!(drained, Drained { handled: true, suspend: false });
+        assert_eq!(app.main_menu_state.selected_option, 4);
+        // Only the first poll waits; the rest just check what is already queued
+        assert_eq!(events.waits[0], Duration::from_millis(50));
+        assert!(events.waits[1..].iter().all(|wait| wait.is_zero()));
+        assert_eq!(events.waits.len(), 5);
+
+        // Nothing queued means nothing to redraw for
+        let drained = drain_events(&mut app, &mut events, Duration::ZERO).unwrap();
+        assert_eq!(drained, Drained::default());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn draining_stops_at_a_suspend_key() {
+        let mut app = App::new(ClientConfig::default());
+        let down = Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        let ctrl_z = Event::Key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        let mut events = ScriptedEvents { queue: vec![down.clone(), ctrl_z, down].into(), waits: Vec::new() };
+
+        let drained = drain_events(&mut app, &mut events, Duration::ZERO).unwrap();
+        assert_eq!(drained, Drained { handled: true, suspend: true });
+        // The key after Ctrl+Z waits for the client to come back
+        assert_eq!(app.main_menu_state.selected_option, 1);
+        assert_eq!(events.queue.len(), 1);
+    }
+
+    #[test]
+    fn quitting_without_saving_discards_world_changes() {
+        use rust_cli_roguelike::common::game_logic::{GameChunkManager, ChunkSettings};
+
+        let world_dir = std::env::temp_dir().join(format!("roguelike_quit_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&world_dir);
+        let open_world = || {
+            let mut chunk_manager = GameChunkManager::with_world_dir(7, ChunkSettings::default(), &world_dir);
+            chunk_manager.set_manual_saves(true);
+            chunk_manager
+        };
+        let quit_with = |key: char| {
+            let mut app = App::new(ClientConfig::default());
+            app.game_mode = GameMode::SinglePlayer;
+            app.chunk_manager = Some(open_world());
+            let chunk_manager = app.chunk_manager.as_mut().unwrap();
+            chunk_manager.set_tile(0, 0, Tile::Road);
+            // Walking off doesn't write the change behind the player's back
+            chunk_manager.update_player_position(2000, 0);
+            assert!(app.has_unsaved_changes());
+
+            app.current_screen = CurrentScreen::Exiting;
+            handle_event(&mut app, Event::Key(KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE)));
+            assert!(app.should_quit);
+        };
+
+        quit_with('y');
+        assert_ne!(open_world().get_tile(0, 0), Some(Tile::Road));
+        quit_with('s');
+        assert_eq!(open_world().get_tile(0, 0), Some(Tile::Road));
+
+        std::fs::remove_dir_all(&world_dir).unwrap();
+    }
+
+    #[test]
+    fn autosaved_changes_are_not_reported_as_unsaved() {
+        use rust_cli_roguelike::common::game_logic::{GameChunkManager, ChunkSettings};
+
+        let world_dir = std::env::temp_dir().join(format!("roguelike_autosave_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&world_dir);
+        let open_world = || {
+            let mut chunk_manager = GameChunkManager::with_world_dir(7, ChunkSettings::default(), &world_dir);
+            chunk_manager.set_manual_saves(true);
+            chunk_manager
+        };
+        let mut app = App::new(ClientConfig { autosave_secs: 60, ..ClientConfig::default() });
+        app.game_mode = GameMode::SinglePlayer;
+        app.chunk_manager = Some(open_world());
+        app.chunk_manager.as_mut().unwrap().set_tile(0, 0, Tile::Road);
+        assert!(!app.autosave_if_due(), "not due yet");
+        assert!(app.has_unsaved_changes());
+
+        app.last_autosave = Instant::now() - Duration::from_secs(61);
+        assert!(app.autosave_if_due());
+        assert!(app.saved_at.is_some());
+        // Quitting now has nothing to lose, and the next session finds the change
+        assert!(!app.has_unsaved_changes());
+        app.current_screen = CurrentScreen::Exiting;
+        handle_event(&mut app, Event::Key(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE)));
+        assert!(app.should_quit);
+        assert_eq!(open_world().get_tile(0, 0), Some(Tile::Road));
+
+        std::fs::remove_dir_all(&world_dir).unwrap();
+    }
+
+    #[test]
+    fn the_bell_only_rings_for_chosen_events() {
+        use crate::notify::NotifyEvent;
+
+        let config: ClientConfig = serde_json::from_str(r#"{"bell": true, "bell_events": ["chat_mention"]}"#).unwrap();
+        assert!(config.rings_bell_for(NotifyEvent::ChatMention));
+        assert!(!config.rings_bell_for(NotifyEvent::Damage));
+        assert!(!config.rings_bell_for(NotifyEvent::PlayerJoined));
+
+        let quiet = ClientConfig { bell: false, ..config };
+        assert!(!quiet.rings_bell_for(NotifyEvent::ChatMention));
+        assert!(ClientConfig { bell: true, ..ClientConfig::default() }.rings_bell_for(NotifyEvent::Damage));
+    }
+
+    #[test]
+    fn pasted_newlines_and_escape_codes_stay_out_of_chat() {
+        let mut app = App::new(ClientConfig::default());
+        for c in "hi\nthere\x1b[31m red\r\t!".chars() {
+            app.add_char_to_chat(c);
+        }
+        assert_eq!(app.chat_input, "hithere[31m red!");
+
+        for _ in 0..GameConstants::MAX_CHAT_LENGTH {
+            app.add_char_to_chat('é');
+        }
+        assert_eq!(app.chat_input.chars().count(), GameConstants::MAX_CHAT_LENGTH);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_ctrl_z_suspends() {
+        let ctrl_z = Event::Key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert!(suspend::is_suspend_key(&ctrl_z));
+        // Plain `z` still cycles the zoom
+        let z = Event::Key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE));
+        assert!(!suspend::is_suspend_key(&z));
+    }
+
+    #[test]
+    fn chat_scrollback_keeps_its_place_within_the_history() {
+        let mut app = App::new(ClientConfig { chat_history: 20, ..ClientConfig::default() });
+        app.current_screen = CurrentScreen::Game;
+        for i in 0..30 {
+            app.record_chat("Ann".to_string(), format!("message {}", i));
+        }
+        assert_eq!(app.chat_messages.len(), 20);
+        assert_eq!(app.chat_messages[0].1, "message 10");
+
+        let page_up = Event::Key(KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE));
+        handle_event(&mut app, page_up.clone());
+        handle_event(&mut app, page_up.clone());
+        assert_eq!(app.chat_scroll, 10);
+
+        // A new message doesn't shift what is on screen
+        app.record_chat("Bob".to_string(), "hi".to_string());
+        assert_eq!(app.chat_scroll, 11);
+
+        for _ in 0..5 {
+            handle_event(&mut app, page_up.clone());
+        }
+        assert_eq!(app.chat_scroll, 19, "stops at the oldest message");
+        handle_event(&mut app, Event::Key(KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE)));
+        assert_eq!(app.chat_scroll, 14);
+    }
+
+    #[test]
+    fn sprinting_stops_at_walls_and_points_of_interest() {
+        let mut app = App::new(ClientConfig::default());
+        app.current_screen = CurrentScreen::Game;
+        app.current_map_type = MapType::Dungeon;
+        app.game_map = GameMap::new(12, 3);
+        for x in 0..11 {
+            app.game_map.tiles.insert((x, 1), Tile::Floor);
+        }
+        app.game_map.tiles.insert((11, 1), Tile::Wall);
+        app.game_map.tiles.insert((3, 1), Tile::Door);
+        app.player.x = 0;
+        app.player.y = 1;
+
+        let shift_right = Event::Key(KeyEvent::new(KeyCode::Char('L'), KeyModifiers::SHIFT));
+        handle_event(&mut app, shift_right.clone());
+        assert_eq!((app.player.x, app.turn_count), (3, 3), "stops on the door");
+        handle_event(&mut app, shift_right);
+        assert_eq!((app.player.x, app.turn_count), (10, 10), "runs on to the wall");
+    }
+
+    /// A client wired to channels instead of a socket: what it sends to the server,
+    /// and where to put what the server sends back. Needs a tokio runtime.
+    fn channel_client() -> (
+        app::NetworkClient,
+        tokio::sync::mpsc::UnboundedReceiver<protocol::ClientMessage>,
+        tokio::sync::mpsc::UnboundedSender<protocol::ServerMessage>,
+    ) {
+        let (client_sender, to_server) = tokio::sync::mpsc::unbounded_channel();
+        let (from_server, server_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let client = app::NetworkClient {
+            sender: client_sender,
+            receiver: server_receiver,
+            player_id: Some("me".to_string()),
+            game_state: None,
+            messages: Vec::new(),
+            multiplayer_chunks: std::collections::HashMap::new(),
+            dungeon_map: None,
+            notifications: Vec::new(),
+            chat_history: Vec::new(),
+            receive_task: tokio::spawn(async {}),
+        };
+        (client, to_server, from_server)
+    }
+
+    #[tokio::test]
+    async fn reconnecting_replaces_the_chat_log_with_the_rooms_history() {
+        use rust_cli_roguelike::common::protocol::ServerMessage;
+
+        let history = |lines: &[&str]| ServerMessage::ChatHistory {
+            messages: lines.iter().map(|line| ("Ann".to_string(), line.to_string())).collect(),
+        };
+        let mut app = App::new(ClientConfig::default());
+        let (client, _to_server, from_server) = channel_client();
+        app.start_multiplayer(client);
+        from_server.send(history(&["one", "two"])).unwrap();
+        app.process_network_messages();
+        app.scroll_chat(true);
+        assert_eq!(app.chat_messages.len(), 2);
+
+        // The server sends the whole recent history again, which must not pile up
+        app.disconnect();
+        assert!(app.chat_messages.is_empty());
+        let (client, _to_server, from_server) = channel_client();
+        app.start_multiplayer(client);
+        from_server.send(history(&["one", "two", "three"])).unwrap();
+        app.process_network_messages();
+        let lines: Vec<&str> = app.chat_messages.iter().map(|(_, message)| message.as_str()).collect();
+        assert_eq!(lines, ["one", "two", "three"]);
+        assert_eq!(app.chat_scroll, 0);
+    }
+
+    #[tokio::test]
+    async fn a_dungeon_map_missing_on_entry_is_requested_once() {
+        use std::collections::HashMap;
+        use rust_cli_roguelike::common::game_logic::GameLogic;
+        use rust_cli_roguelike::common::protocol::{ClientMessage, GameState, NetworkCurrentScreen, NetworkPlayer, ServerMessage};
+
+        let (client, mut to_server, from_server) = channel_client();
+        let mut app = App::new(ClientConfig::default());
+        app.start_multiplayer(client);
+        let state_in = |map_type| ServerMessage::GameState {
+            state: GameState {
+                players: HashMap::from([("me".to_string(), NetworkPlayer {
+                    id: "me".to_string(),
+                    name: "Me".to_string(),
+                    x: 1,
+                    y: 1,
+                    hp: 10,
+                    max_hp: 10,
+                    symbol: '@',
+                    current_screen: NetworkCurrentScreen::Game,
+                    color: (255, 255, 0),
+                    current_map_type: map_type,
+                    dungeon_entrance_pos: Some((0, 0)),
+                    inventory: Vec::new(),
+                })]),
+                turn_count: 0,
+            },
+        };
+        let dungeon = GameLogic::generate_dungeon_map_with_seed(1);
+        let dungeon_data = || ServerMessage::DungeonData { dungeon_map: GameLogic::game_map_to_network(&dungeon) };
+        let mut dungeon_requests = || {
+            std::iter::from_fn(|| to_server.try_recv().ok())
+                .filter(|message| matches!(message, ClientMessage::RequestDungeonData))
+                .count()
+        };
+
+        // The map arriving with the entry needs no request
+        app.enter_dungeon();
+        from_server.send(dungeon_data()).unwrap();
+        from_server.send(state_in(MapType::Dungeon)).unwrap();
+        app.process_network_messages();
+        assert_eq!(app.game_map.tiles, dungeon.tiles);
+        assert_eq!(dungeon_requests(), 0);
+
+        from_server.send(state_in(MapType::Overworld)).unwrap();
+        app.process_network_messages();
+        assert!(app.game_map.tiles.is_empty());
+
+        // Entering without the map asks for it, and only until it comes
+        app.enter_dungeon();
+        from_server.send(state_in(MapType::Dungeon)).unwrap();
+        app.process_network_messages();
+        assert_eq!(app.current_map_type, MapType::Dungeon);
+        assert_eq!(dungeon_requests(), 1);
+        from_server.send(dungeon_data()).unwrap();
+        from_server.send(state_in(MapType::Dungeon)).unwrap();
+        app.process_network_messages();
+        assert_eq!(app.game_map.tiles, dungeon.tiles);
+        assert_eq!(dungeon_requests(), 0);
+    }
+}
@@ -1,12 +1,14 @@
 use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use std::error::Error;
+use std::io;
+use tokio_tungstenite::{connect_async, tungstenite::{Error as WsError, Message}};
 use futures_util::{SinkExt, StreamExt};
 
 use crate::protocol::{ClientMessage, ServerMessage};
 use crate::app::NetworkClient;
 
 impl NetworkClient {
-    pub async fn connect(server_address: &str, player_name: String) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn connect(server_address: &str, player_name: String, room: Option<String>) -> Result<Self, Box<dyn Error + Send + Sync>> {
         let url = format!("ws://{}", server_address);
         let (ws_stream, _) = connect_async(&url).await?;
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
@@ -19,17 +21,19 @@ impl NetworkClient {
             while let Some(msg) = client_receiver.recv().await {
                 let json = serde_json::to_string(&msg).unwrap();
                 if ws_sender.send(Message::Text(json)).await.is_err() {
-                    break;
+                    return;
                 }
             }
+            // The client was dropped; everything queued has been sent
+            let _ = ws_sender.close().await;
         });
 
         // Handle incoming messages from server
-        tokio::spawn(async move {
+        let receive_task = tokio::spawn(async move {
             while let Some(msg) = ws_receiver.next().await {
                 match msg {
                     Ok(Message::Text(text)) => {
-                        if let Ok(server_msg) = serde_json::from_str::<ServerMessage>(&text) {
+                        if let Ok(server_msg) = ServerMessage::parse(&text) {
                             if server_sender.send(server_msg).is_err() {
                                 break;
                             }
@@ -49,11 +53,76 @@ impl NetworkClient {
             messages: Vec::new(),
             multiplayer_chunks: std::collections::HashMap::new(),
             dungeon_map: None,
+            notifications: Vec::new(),
+            chat_history: Vec::new(),
+            receive_task,
         };
 
         // Send initial connect message
-        client.sender.send(ClientMessage::Connect { player_name })?;
+        client.sender.send(ClientMessage::Connect { player_name, room })?;
 
         Ok(client)
     }
 }
+
+/// Turn a failed connection attempt into a message a player can act on
+pub fn describe_connect_error(error: &(dyn Error + Send + Sync + 'static)) -> String {
+    match error.downcast_ref::<WsError>() {
+        Some(WsError::Io(e)) if e.kind() == io::ErrorKind::ConnectionRefused => {
+            "connection refused (is the server running?)".to_string()
+        }
+        Some(WsError::Io(e)) => format!("network error: {}", e),
+        Some(WsError::Url(e)) => format!("invalid server address: {}", e),
+        Some(e) => format!("handshake failed: {}", e),
+        None => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    #[tokio::test]
+    async fn disconnecting_closes_the_socket_and_stops_the_tasks() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let metrics = tokio::runtime::Handle::current().metrics();
+        let tasks_before = metrics.num_alive_tasks();
+
+        // The server side stays open, so only the client can end its tasks
+        let mut connections = Vec::new();
+        for _ in 0..5 {
+            let (server, client) = tokio::join!(
+                async { accept_async(listener.accept().await.unwrap().0).await.unwrap() },
+                NetworkClient::connect(&address, "tester".to_string(), None)
+            );
+            let (server_sender, mut server_receiver) = server.split();
+            client.unwrap().disconnect();
+
+            let mut received = Vec::new();
+            while received.len() < 2 {
+                match server_receiver.next().await {
+                    Some(Ok(Message::Text(text))) => received.push(serde_json::from_str::<ClientMessage>(&text).unwrap()),
+                    other => panic!("expected the connect and goodbye messages, got {:?}", other),
+                }
+            }
+            assert!(matches!(received.as_slice(), [ClientMessage::Connect { .. }, ClientMessage::Disconnect]));
+            connections.push((server_sender, server_receiver));
+        }
+
+        let stopped = tokio::time::timeout(Duration::from_secs(5), async {
+            while metrics.num_alive_tasks() > tasks_before {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await;
+        assert!(stopped.is_ok(), "{} network tasks are still running", metrics.num_alive_tasks() - tasks_before);
+
+        for (_, mut server_receiver) in connections {
+            let next = tokio::time::timeout(Duration::from_secs(5), server_receiver.next()).await;
+            assert!(matches!(next, Ok(Some(Ok(Message::Close(_))))), "the client never closed its socket");
+        }
+    }
+}
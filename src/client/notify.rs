@@ -0,0 +1,25 @@
+use std::io::{self, Write};
+use serde::{Deserialize, Serialize};
+
+use crate::config::ClientConfig;
+
+/// Events worth an audible cue, since the message log is easy to miss
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    Damage,
+    PlayerJoined,
+    ChatMention,
+}
+
+/// Ring the terminal bell for an event if the config wants a cue for it
+pub fn notify(config: &ClientConfig, event: NotifyEvent) {
+    if !config.rings_bell_for(event) {
+        return;
+    }
+
+    // BEL doesn't move the cursor, so it's safe to write alongside the TUI
+    let mut stderr = io::stderr();
+    let _ = stderr.write_all(b"\x07");
+    let _ = stderr.flush();
+}
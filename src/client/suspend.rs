@@ -0,0 +1,65 @@
+// Backgrounding the client (Ctrl-Z) without leaving the shell in raw mode
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use ratatui::{
+    backend::Backend,
+    crossterm::{
+        event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    Terminal,
+};
+use signal_hook::consts::{SIGSTOP, SIGTSTP};
+
+/// Raw mode turns off the terminal's own job control, so Ctrl-Z arrives as a
+/// plain key press instead of stopping the process
+pub fn is_suspend_key(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Key(key)
+            if key.kind == KeyEventKind::Press
+                && key.code == KeyCode::Char('z')
+                && key.modifiers.contains(KeyModifiers::CONTROL)
+    )
+}
+
+/// Remembers a SIGTSTP sent from outside (e.g. `kill -TSTP`) so the client can
+/// put the terminal back before stopping
+pub struct SuspendSignal(Arc<AtomicBool>);
+
+impl SuspendSignal {
+    pub fn register() -> io::Result<Self> {
+        let flag = Arc::new(AtomicBool::new(false));
+        signal_hook::flag::register(SIGTSTP, Arc::clone(&flag))?;
+        Ok(Self(flag))
+    }
+
+    /// Whether a SIGTSTP arrived since the last call
+    pub fn take(&self) -> bool {
+        self.0.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Hand the terminal back to the shell, stop until resumed with `fg`, then take
+/// it over again. Clearing the terminal makes the next draw repaint everything.
+pub fn suspend<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stderr(), LeaveAlternateScreen, DisableMouseCapture)?;
+    terminal.show_cursor()?;
+
+    // SIGTSTP has our handler installed, so stop with the uncatchable SIGSTOP
+    signal_hook::low_level::raise(SIGSTOP)?;
+
+    // Execution continues here once the shell sends SIGCONT
+    enable_raw_mode()?;
+    execute!(io::stderr(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.hide_cursor()?;
+    terminal.clear()
+}
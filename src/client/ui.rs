@@ -6,9 +6,22 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::{App, CurrentScreen, MapType, Tile, GameMode};
+use crate::app::{App, CurrentScreen, MapType, MessageKind, Tile, GameMode};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rust_cli_roguelike::common::constants::GameConstants;
+use rust_cli_roguelike::common::game_logic::GameLogic;
+use rust_cli_roguelike::common::item::Item;
 
 pub fn ui(frame: &mut Frame, app: &mut App) {
+    // The map can't shrink below its minimum viewport, so ask for a bigger window instead
+    let area = frame.area();
+    if app.current_screen != CurrentScreen::MainMenu
+        && (area.width < GameConstants::MIN_TERMINAL_WIDTH || area.height < GameConstants::MIN_TERMINAL_HEIGHT)
+    {
+        render_too_small(frame, area);
+        return;
+    }
+
     match app.current_screen {
         CurrentScreen::MainMenu => render_main_menu(frame, app),
         CurrentScreen::Chat => render_chat_screen(frame, app),
@@ -37,12 +50,16 @@ fn render_main_menu(frame: &mut Frame, app: &App) {
     frame.render_widget(title, chunks[0]);
 
     // Menu options
+    let difficulty = format!("Difficulty: {}", app.difficulty.name());
     let menu_items = if app.main_menu_state.username_input_mode {
         vec!["[Press Enter to confirm, Esc to cancel]"]
     } else {
         vec![
             "Single Player",
+            "Survival",
+            difficulty.as_str(),
             "Multiplayer", 
+            "LAN Games",
             "Set Username",
             "Quit",
         ]
@@ -50,7 +67,29 @@ fn render_main_menu(frame: &mut Frame, app: &App) {
 
     let mut menu_list_items = Vec::<ListItem>::new();
     
-    if app.main_menu_state.username_input_mode {
+    if let Some(ref browser) = app.lan_browser {
+        // Servers heard on the local network
+        if browser.servers.is_empty() {
+            menu_list_items.push(ListItem::new(Line::from(Span::styled(
+                "Searching for LAN games...",
+                Style::default().fg(Color::Gray),
+            ))));
+        }
+        for (i, server) in browser.servers.iter().enumerate() {
+            let selected = i == browser.selected;
+            let style = if selected {
+                Style::default().fg(Color::Yellow).bg(Color::DarkGray)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let prefix = if selected { "▶ " } else { "  " };
+            let players = if server.players == 1 { "player" } else { "players" };
+            menu_list_items.push(ListItem::new(Line::from(Span::styled(
+                format!("{}{} ({} {})", prefix, server.address, server.players, players),
+                style,
+            ))));
+        }
+    } else if app.main_menu_state.username_input_mode {
         // Username input mode
         menu_list_items.push(ListItem::new(Line::from(Span::styled(
             format!("Username: {}", app.main_menu_state.username_input),
@@ -79,7 +118,9 @@ fn render_main_menu(frame: &mut Frame, app: &App) {
 
     let menu_list = List::new(menu_list_items)
         .block(Block::default().borders(Borders::ALL).title(
-            if app.main_menu_state.username_input_mode {
+            if app.lan_browser.is_some() {
+                "LAN Games (↑/↓ to select, Enter to join, Esc to go back)"
+            } else if app.main_menu_state.username_input_mode {
                 "Enter Username"
             } else {
                 "Select Option (↑/↓ to select, Enter to confirm)"
@@ -89,17 +130,21 @@ fn render_main_menu(frame: &mut Frame, app: &App) {
     frame.render_widget(menu_list, chunks[1]);
 
     // Status/Error
-    let status_text = if app.main_menu_state.connecting {
-        format!("Connecting to server {}...", app.server_address)
+    let status_text = if let Some(started) = app.main_menu_state.connecting {
+        let spinner = ['|', '/', '-', '\\'][(started.elapsed().as_millis() / 150) as usize % 4];
+        format!("Connecting to server {}... {} (Esc to cancel)", app.server_address, spinner)
     } else if let Some(ref error) = app.main_menu_state.connection_error {
         format!("Error: {}", error)
+    } else if let Some(ref notice) = app.main_menu_state.notice {
+        notice.clone()
     } else {
-        format!("Server: {} | Player: {} | Press Q to quit", app.server_address, app.player_name)
+        let room = app.config.room.as_deref().unwrap_or(GameConstants::DEFAULT_ROOM);
+        format!("Server: {} | Room: {} | Player: {} | Press Q to quit", app.server_address, room, app.player_name)
     };
 
     let status_color = if app.main_menu_state.connection_error.is_some() {
         Color::Red
-    } else if app.main_menu_state.connecting {
+    } else if app.main_menu_state.connecting.is_some() {
         Color::Yellow
     } else {
         Color::Cyan
@@ -114,6 +159,28 @@ fn render_main_menu(frame: &mut Frame, app: &App) {
     frame.render_widget(status, chunks[2]);
 }
 
+fn render_too_small(frame: &mut Frame, area: Rect) {
+    let message = format!(
+        "Terminal too small ({}x{}).\nResize to at least {}x{}.",
+        area.width,
+        area.height,
+        GameConstants::MIN_TERMINAL_WIDTH,
+        GameConstants::MIN_TERMINAL_HEIGHT,
+    );
+    let paragraph = Paragraph::new(Text::styled(message, Style::default().fg(Color::Yellow)))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, area);
+}
+
+/// Reminder of what the tile the player stands on lets them do
+fn standing_hint(tile: Tile) -> Option<&'static str> {
+    match tile {
+        Tile::DungeonEntrance => Some("Press E to enter"),
+        Tile::DungeonExit | Tile::DungeonShortcut => Some("Press X to exit"),
+        _ => None,
+    }
+}
+
 fn render_game_ui(frame: &mut Frame, app: &mut App) {
     // Create the layout sections based on chat input mode
     let constraints = if app.chat_input_mode && app.game_mode == GameMode::MultiPlayer {
@@ -138,13 +205,19 @@ fn render_game_ui(frame: &mut Frame, app: &mut App) {
 
     // Status bar showing player stats and current screen
     let mode_text = match app.game_mode {
+        GameMode::SinglePlayer if app.survival => "Survival",
         GameMode::SinglePlayer => "Single Player",
         GameMode::MultiPlayer => "Multiplayer",
     };
+    let hunger_text = if app.survival {
+        format!(" | Hunger: {}/{}", app.player.hunger, GameConstants::MAX_HUNGER)
+    } else {
+        String::new()
+    };
     
     let status_text = if app.game_mode == GameMode::MultiPlayer {
         format!(
-            "HP: {}/{} | Turn: {} | Map: {} | Position: ({}, {}) | Mode: {} | Controls: HJKL/Arrows (move), E (enter dungeon), X (exit dungeon), I (inventory), C (chat), Q (quit)",
+            "HP: {}/{} | Turn: {} | Map: {} | Position: ({}, {}){} | Mode: {} | Controls: HJKL/Arrows (move), E (enter dungeon), X (exit dungeon), F (chop tree), R (lay road), Z (zoom), T (fast travel), I (inventory), C (chat), ? (help), Q (quit)",
             app.player.hp, 
             app.player.max_hp, 
             app.turn_count, 
@@ -154,13 +227,15 @@ fn render_game_ui(frame: &mut Frame, app: &mut App) {
             },
             app.player.x,
             app.player.y,
+            nearby_players_text(app),
             mode_text
         )
     } else {
         format!(
-            "HP: {}/{} | Turn: {} | Map: {} | Position: ({}, {}) | Mode: {} | Controls: HJKL/Arrows (move), E (enter dungeon), X (exit dungeon), I (inventory), Q (quit)",
+            "HP: {}/{}{} | Turn: {} | Map: {} | Position: ({}, {}) | Mode: {} ({}) | Controls: HJKL/Arrows (move), E (enter dungeon), X (exit dungeon), F (chop tree), R (lay road), Z (zoom), T (fast travel), I (inventory), ? (help), Q (quit)",
             app.player.hp, 
             app.player.max_hp, 
+            hunger_text, 
             app.turn_count, 
             match app.current_map_type {
                 MapType::Overworld => "Overworld",
@@ -168,13 +243,20 @@ fn render_game_ui(frame: &mut Frame, app: &mut App) {
             },
             app.player.x,
             app.player.y,
-            mode_text
+            mode_text,
+            app.difficulty.name()
         )
     };
     
+    let status_title = if app.saved_at.is_some() { "Status (Saved)" } else { "Status" };
+    // Kept in the title so it stays visible however much of the status line fits
+    let mut title_spans = vec![Span::raw(status_title)];
+    if let Some(hint) = app.tile_under_player().and_then(standing_hint) {
+        title_spans.push(Span::styled(format!(" | {} ", hint), Style::default().fg(Color::Yellow)));
+    }
     let status_block = Block::default()
         .borders(Borders::ALL)
-        .title("Status")
+        .title(Line::from(title_spans))
         .style(Style::default());
 
     let status = Paragraph::new(Text::styled(
@@ -207,6 +289,8 @@ fn render_game_ui(frame: &mut Frame, app: &mut App) {
             }
         },
         CurrentScreen::Inventory => render_inventory(frame, app, chunks[1]),
+        CurrentScreen::FastTravel => render_fast_travel(frame, app, chunks[1]),
+        CurrentScreen::Help => render_help(frame, chunks[1]),
         CurrentScreen::Exiting => render_exit_screen(frame, app, chunks[1]),
     }
 
@@ -215,51 +299,67 @@ fn render_game_ui(frame: &mut Frame, app: &mut App) {
         render_chat_input_bar(frame, app, chunks[2]);
         
         // Message log is now at index 3
-        let mut message_items = Vec::<ListItem>::new();
-        for message in app.messages.iter().rev().take(3) {
-            message_items.push(ListItem::new(Line::from(Span::styled(
-                message.clone(),
-                Style::default().fg(Color::Cyan),
-            ))));
-        }
-
-        let message_list = List::new(message_items)
-            .block(Block::default().borders(Borders::ALL).title("Messages"));
-
-        frame.render_widget(message_list, chunks[3]);
+        render_message_log(frame, app, chunks[3]);
     } else {
         // Message log at normal position when not in chat input mode
-        let mut message_items = Vec::<ListItem>::new();
-        for message in app.messages.iter().rev().take(3) {
-            message_items.push(ListItem::new(Line::from(Span::styled(
-                message.clone(),
-                Style::default().fg(Color::Cyan),
-            ))));
-        }
+        render_message_log(frame, app, chunks[2]);
+    }
+}
+fn message_color(kind: MessageKind) -> Color {
+    match kind {
+        MessageKind::Combat => Color::Rgb(255, 140, 0), // Orange
+        MessageKind::System => Color::Cyan,
+        MessageKind::Interaction => Color::Gray,
+        MessageKind::Error => Color::Red,
+    }
+}
 
-        let message_list = List::new(message_items)
-            .block(Block::default().borders(Borders::ALL).title("Messages"));
+/// The latest messages, newest first, limited to the chosen kind if filtering
+fn render_message_log(frame: &mut Frame, app: &App, area: Rect) {
+    let message_items: Vec<ListItem> = app.messages.iter()
+        .rev()
+        .filter(|message| app.message_filter.is_none_or(|kind| message.kind == kind))
+        .take(3)
+        .map(|message| ListItem::new(Line::from(Span::styled(
+            message.text.clone(),
+            Style::default().fg(message_color(message.kind)),
+        ))))
+        .collect();
 
-        frame.render_widget(message_list, chunks[2]);
-    }
+    let title = match app.message_filter {
+        Some(kind) => format!("Messages ({} only, o to change)", kind.name()),
+        None => "Messages".to_string(),
+    };
+    let message_list = List::new(message_items)
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    frame.render_widget(message_list, area);
 }
+
 fn render_game_map(frame: &mut Frame, app: &mut App, area: Rect) {
-    // Calculate the viewport size (accounting for borders)
-    let viewport_width = (area.width.saturating_sub(2)) as i32; // Subtract 2 for borders
-    let viewport_height = (area.height.saturating_sub(2)) as i32; // Subtract 2 for borders
+    // Calculate the viewport size in terminal cells (accounting for borders)
+    let viewport_cols = (area.width.saturating_sub(2)) as i32; // Subtract 2 for borders
+    let viewport_rows = (area.height.saturating_sub(2)) as i32; // Subtract 2 for borders
     
     // Ensure minimum viewport size and make width wider to utilize terminal space better
-    let viewport_width = viewport_width.max(60); // Increased minimum width
-    let viewport_height = viewport_height.max(20); // Increased minimum height
+    let viewport_cols = viewport_cols.max(GameConstants::VIEWPORT_MIN_WIDTH); // Increased minimum width
+    let viewport_rows = viewport_rows.max(GameConstants::VIEWPORT_MIN_HEIGHT); // Increased minimum height
+
+    // Each tile may span several cells when zoomed, so fewer tiles fit
+    let (cell_width, cell_height) = app.zoom.cell_size();
+    let viewport_width = viewport_cols / cell_width;
+    let viewport_height = viewport_rows / cell_height;
     
     // Calculate camera position to center on player
     let camera_x = app.player.x - viewport_width / 2;
     let camera_y = app.player.y - viewport_height / 2;
     
     // Update chunk manager with player position if available
+    app.ensure_overworld_loaded();
     if let Some(ref mut chunk_manager) = app.chunk_manager {
         chunk_manager.update_player_position(app.player.x, app.player.y);
     }
+    app.reveal_dungeon_around_player();
     
     let mut lines = Vec::<Line>::new();
     
@@ -273,7 +373,7 @@ fn render_game_map(frame: &mut Frame, app: &mut App, area: Rect) {
             if world_x == app.player.x && world_y == app.player.y {
                 // Player character with bright yellow foreground and dark background
                 spans.push(Span::styled(
-                    app.player.symbol.to_string(),
+                    zoomed_glyph(app.player.symbol, false, cell_width),
                     Style::default()
                         .fg(Color::Yellow)
                         .bg(Color::DarkGray)
@@ -282,7 +382,7 @@ fn render_game_map(frame: &mut Frame, app: &mut App, area: Rect) {
                 // Other players in multiplayer mode - only show players in the same map
                 let player_color = Color::Rgb(other_player.color.0, other_player.color.1, other_player.color.2);
                 spans.push(Span::styled(
-                    other_player.symbol.to_string(),
+                    zoomed_glyph(other_player.symbol, false, cell_width),
                     Style::default()
                         .fg(player_color)
                 ));
@@ -294,13 +394,13 @@ fn render_game_map(frame: &mut Frame, app: &mut App, area: Rect) {
                         chunk_manager.get_tile(world_x, world_y)
                     } else {
                         // Fall back to traditional game map
-                        app.game_map.tiles.get(&(world_x, world_y)).copied()
+                        app.game_map.tile_at(world_x, world_y)
                     }
                 } else {
                     // Multiplayer: check if in dungeon first, then use appropriate map source
                     if app.current_map_type == MapType::Dungeon {
                         // In dungeon: use the traditional game map
-                        app.game_map.tiles.get(&(world_x, world_y)).copied()
+                        app.game_map.tile_at(world_x, world_y)
                     } else {
                         // In overworld: try multiplayer chunks first, then traditional map
                         app.get_multiplayer_tile(world_x, world_y).or_else(|| 
@@ -309,16 +409,34 @@ fn render_game_map(frame: &mut Frame, app: &mut App, area: Rect) {
                     }
                 };
                 
-                if let Some(tile) = tile {
+                if let Some((color, age)) = trail_step_at(app, world_x, world_y) {
+                    // Keep the tile's background so the trail doesn't hide the terrain type
+                    let background = tile.map(|tile| get_tile_style_and_char(tile).0).unwrap_or_default();
+                    spans.push(Span::styled(
+                        zoomed_glyph('·', false, cell_width),
+                        background.fg(faded_color(color, age, GameConstants::PLAYER_TRAIL_LENGTH)),
+                    ));
+                } else if let Some(tile) = tile {
                     let (style, character) = get_tile_style_and_char(tile);
-                    spans.push(Span::styled(character.to_string(), style));
+                    spans.push(Span::styled(zoomed_glyph(character, true, cell_width), style));
+                } else if app.current_map_type == MapType::Dungeon
+                    && !GameLogic::is_within_dungeon_bounds(&app.game_map, world_x, world_y)
+                {
+                    // Past the edge of the finite dungeon - shade it so it can't be mistaken for unexplored space
+                    spans.push(Span::styled(zoomed_glyph('░', true, cell_width), Style::default().fg(Color::DarkGray).bg(Color::Black)));
                 } else {
-                    // Out of bounds or empty space - show void
-                    spans.push(Span::styled(" ".to_string(), Style::default().bg(Color::Black)));
+                    // Empty space - show void
+                    spans.push(Span::styled(zoomed_glyph(' ', true, cell_width), Style::default().bg(Color::Black)));
                 }
             }
         }
-        lines.push(Line::from(spans));
+
+        // Tall tiles repeat the whole row of spans
+        let line = Line::from(spans);
+        for _ in 1..cell_height {
+            lines.push(line.clone());
+        }
+        lines.push(line);
     }
 
     let title = match app.current_map_type {
@@ -340,6 +458,15 @@ fn render_game_map(frame: &mut Frame, app: &mut App, area: Rect) {
         },
     };
 
+    // Fade the new map in after entering or leaving a dungeon
+    if let Some(brightness) = app.map_transition_progress() {
+        for line in &mut lines {
+            for span in &mut line.spans {
+                span.style = apply_brightness_to_style(span.style, brightness);
+            }
+        }
+    }
+
     let game_block = Block::default()
         .borders(Borders::ALL)
         .title(title)
@@ -349,6 +476,182 @@ fn render_game_map(frame: &mut Frame, app: &mut App, area: Rect) {
         .block(game_block);
 
     frame.render_widget(game_area, area);
+
+    if app.show_coordinate_grid {
+        render_coordinate_grid(frame, app, area, (camera_x, camera_y), (viewport_width, viewport_height));
+    }
+    if app.show_name_tags {
+        render_name_tags(frame, app, area, (camera_x, camera_y), (viewport_width, viewport_height));
+    }
+    if app.show_dungeon_minimap && app.current_map_type == MapType::Dungeon {
+        render_dungeon_minimap(frame, app, area);
+    }
+    if app.config.compass && app.current_map_type == MapType::Overworld {
+        render_compass(frame, app, area);
+    }
+}
+
+/// Faint "x,y" labels on every grid point of world coordinates in view
+fn render_coordinate_grid(frame: &mut Frame, app: &App, area: Rect, (camera_x, camera_y): (i32, i32), (viewport_width, viewport_height): (i32, i32)) {
+    let spacing = GameConstants::COORDINATE_GRID_SPACING;
+    let (cell_width, cell_height) = app.zoom.cell_size();
+    let inner_right = area.x + area.width.saturating_sub(1); // Stop before the right border
+    let style = Style::default().fg(Color::DarkGray);
+
+    for viewport_y in 0..viewport_height {
+        let world_y = camera_y + viewport_y;
+        if world_y.rem_euclid(spacing) != 0 {
+            continue;
+        }
+        let row = area.y + 1 + (viewport_y * cell_height) as u16;
+        if row >= area.y + area.height.saturating_sub(1) {
+            break;
+        }
+        for viewport_x in 0..viewport_width {
+            let world_x = camera_x + viewport_x;
+            if world_x.rem_euclid(spacing) != 0 {
+                continue;
+            }
+            let column = area.x + 1 + (viewport_x * cell_width) as u16;
+            if column >= inner_right {
+                break;
+            }
+            // Only the foreground changes so the terrain colour still shows through
+            let label = format!("{},{}", world_x, world_y);
+            frame.buffer_mut().set_stringn(column, row, label, (inner_right - column) as usize, style);
+        }
+    }
+}
+
+/// Names of the nearby players on the row above them, in their colour. Closer
+/// players are labelled first; a label that would overlap another label or
+/// cover a player is left out rather than drawn on top.
+fn render_name_tags(frame: &mut Frame, app: &App, area: Rect, (camera_x, camera_y): (i32, i32), (viewport_width, viewport_height): (i32, i32)) {
+    let (cell_width, cell_height) = app.zoom.cell_size();
+    let inner_left = area.x as i32 + 1;
+    let inner_right = (area.x + area.width.saturating_sub(1)) as i32; // Stop before the right border
+    let on_screen = |x: i32, y: i32| {
+        let (viewport_x, viewport_y) = (x - camera_x, y - camera_y);
+        (viewport_x >= 0 && viewport_x < viewport_width && viewport_y >= 0 && viewport_y < viewport_height).then(|| (
+            inner_left + viewport_x * cell_width,
+            area.y as i32 + 1 + viewport_y * cell_height,
+        ))
+    };
+
+    let mut players: Vec<_> = app.other_players.values()
+        .filter(|p| p.current_map_type == app.current_map_type)
+        .filter(|p| (p.x - app.player.x).abs().max((p.y - app.player.y).abs()) <= GameConstants::NAME_TAG_RADIUS)
+        .filter_map(|p| on_screen(p.x, p.y).map(|cell| (p, cell)))
+        .collect();
+    players.sort_by_key(|(p, _)| (p.x - app.player.x).abs().max((p.y - app.player.y).abs()));
+
+    // Cells already taken, as (row, first column, last column)
+    let mut taken: Vec<(i32, i32, i32)> = players.iter()
+        .map(|(_, (column, row))| (*row, *column, column + cell_width - 1))
+        .chain(on_screen(app.player.x, app.player.y).map(|(column, row)| (row, column, column + cell_width - 1)))
+        .collect();
+
+    for (player, (column, row)) in players {
+        let label: String = player.name.chars().take(GameConstants::NAME_TAG_MAX_LEN).collect();
+        let width = label.chars().count() as i32;
+        let label_row = row - 1;
+        if label_row <= area.y as i32 || width > inner_right - inner_left {
+            continue; // No room above the player
+        }
+        let first = (column + cell_width / 2 - width / 2).clamp(inner_left, inner_right - width);
+        let last = first + width - 1;
+        if taken.iter().any(|&(r, start, end)| r == label_row && start <= last && first <= end) {
+            continue;
+        }
+        taken.push((label_row, first, last));
+
+        let color = Color::Rgb(player.color.0, player.color.1, player.color.2);
+        frame.buffer_mut().set_string(first as u16, label_row as u16, label, Style::default().fg(color).bg(Color::Black));
+    }
+}
+
+/// Compact schematic of the explored parts of the dungeon in the map's top right corner
+fn render_dungeon_minimap(frame: &mut Frame, app: &App, area: Rect) {
+    let scale = GameConstants::DUNGEON_MINIMAP_SCALE;
+    let columns = (app.game_map.width + scale - 1) / scale;
+    let rows = (app.game_map.height + scale - 1) / scale;
+    let width = (columns + 2) as u16; // Plus borders
+    let height = (rows + 2) as u16;
+    if area.width < width + 2 || area.height < height + 2 {
+        return; // Not worth covering the whole map
+    }
+
+    let mut lines = Vec::<Line>::new();
+    for row in 0..rows {
+        let mut spans = Vec::<Span>::new();
+        for column in 0..columns {
+            let cell_tiles: Vec<(i32, i32)> = (0..scale)
+                .flat_map(|dy| (0..scale).map(move |dx| (column * scale + dx, row * scale + dy)))
+                .collect();
+            let explored = |tile: Tile| cell_tiles.iter().any(|pos| {
+                app.dungeon_explored.contains(pos) && app.game_map.tile_at(pos.0, pos.1) == Some(tile)
+            });
+
+            let (glyph, style) = if cell_tiles.contains(&(app.player.x, app.player.y)) {
+                ('@', Style::default().fg(Color::Yellow))
+            } else if explored(Tile::DungeonExit) {
+                ('<', Style::default().fg(Color::Cyan))
+            } else if explored(Tile::DungeonShortcut) {
+                ('<', Style::default().fg(Color::Green))
+            } else if explored(Tile::Floor) || explored(Tile::Door) {
+                ('·', Style::default().fg(Color::Gray))
+            } else if cell_tiles.iter().any(|pos| app.dungeon_explored.contains(pos)) {
+                ('#', Style::default().fg(Color::DarkGray))
+            } else {
+                (' ', Style::default())
+            };
+            spans.push(Span::styled(glyph.to_string(), style));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let minimap_area = Rect {
+        x: area.x + area.width - width - 1,
+        y: area.y + 1,
+        width,
+        height,
+    };
+    let minimap = Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL).title("Map"))
+        .style(Style::default().bg(Color::Black));
+    frame.render_widget(Clear, minimap_area);
+    frame.render_widget(minimap, minimap_area);
+}
+
+/// Small compass rose with the player's world coordinates in the map's top left corner
+fn render_compass(frame: &mut Frame, app: &App, area: Rect) {
+    let coordinates = format!("{},{}", app.player.x, app.player.y);
+    let width = coordinates.chars().count().max(5) as u16 + 2; // Plus borders
+    let height = 6;
+    if area.width < width + 2 || area.height < height + 2 {
+        return;
+    }
+
+    let axis = Style::default().fg(Color::Gray);
+    let north = Style::default().fg(Color::Red);
+    let lines = vec![
+        Line::from(Span::styled("N", north)).centered(),
+        Line::from(vec![Span::styled("W ", axis), Span::styled("+", north), Span::styled(" E", axis)]).centered(),
+        Line::from(Span::styled("S", axis)).centered(),
+        Line::from(Span::styled(coordinates, Style::default().fg(Color::Yellow))).centered(),
+    ];
+
+    let compass_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width,
+        height,
+    };
+    let compass = Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().bg(Color::Black));
+    frame.render_widget(Clear, compass_area);
+    frame.render_widget(compass, compass_area);
 }
 
 fn render_chat_screen(frame: &mut Frame, app: &mut App) {
@@ -376,7 +679,7 @@ fn render_chat_screen(frame: &mut Frame, app: &mut App) {
     // Collect all messages first with their wrapping
     let mut chat_lines = Vec::new();
     
-    for (player_name, message) in app.chat_messages.iter().rev().take(15) { // Show last 15 messages
+    for (player_name, message) in app.chat_messages.iter().rev().skip(app.chat_scroll).take(15) { // Show 15 messages
         let full_message = format!("{}: {}", player_name, message);
         let wrapped_lines = wrap_text(&full_message, available_width);
         
@@ -426,7 +729,7 @@ fn render_chat_screen(frame: &mut Frame, app: &mut App) {
     chat_lines.reverse();
 
     let chat_paragraph = Paragraph::new(Text::from(chat_lines))
-        .block(Block::default().borders(Borders::ALL).title("Chat Messages"))
+        .block(Block::default().borders(Borders::ALL).title(chat_title(app, "Chat Messages")))
         .wrap(Wrap { trim: false });
     frame.render_widget(chat_paragraph, chunks[1]);
 
@@ -442,13 +745,22 @@ fn render_chat_screen(frame: &mut Frame, app: &mut App) {
 
     // Instructions
     let instructions = Paragraph::new(Text::styled(
-        "Press Enter to send, Esc to close chat",
+        "Press Enter to send, PageUp/PageDown to scroll, Esc to close chat",
         Style::default().fg(Color::Gray),
     ))
     .block(Block::default().borders(Borders::ALL));
     frame.render_widget(instructions, chunks[3]);
 }
 
+/// Chat panel title, noting how far back the player has scrolled
+fn chat_title(app: &App, title: &str) -> String {
+    if app.chat_scroll > 0 {
+        format!("{} - {} newer below", title, app.chat_scroll)
+    } else {
+        title.to_string()
+    }
+}
+
 fn render_chat_widget(frame: &mut Frame, app: &App, area: Rect) {
     // Chat widget for multiplayer mode - use Paragraph with wrapping instead of List
     let mut chat_lines = Vec::<Line>::new();
@@ -461,7 +773,7 @@ fn render_chat_widget(frame: &mut Frame, app: &App, area: Rect) {
     let mut all_messages = Vec::new();
     let mut total_lines = 0;
     
-    for (player_name, message) in app.chat_messages.iter().rev().take(15) {
+    for (player_name, message) in app.chat_messages.iter().rev().skip(app.chat_scroll).take(15) {
         let full_message = format!("{}: {}", player_name, message);
         let wrapped_lines = wrap_text(&full_message, available_width);
         
@@ -519,17 +831,123 @@ fn render_chat_widget(frame: &mut Frame, app: &App, area: Rect) {
         }
     }
 
-    let chat_title = format!("💬 Chat ({})", app.chat_messages.len());
     let chat_paragraph = Paragraph::new(Text::from(chat_lines))
         .block(Block::default()
             .borders(Borders::ALL)
-            .title(chat_title)
+            .title(chat_title(app, &format!("💬 Chat ({})", app.chat_messages.len())))
             .title_style(Style::default().fg(Color::Yellow)))
         .wrap(Wrap { trim: false });
     
     frame.render_widget(chat_paragraph, area);
 }
 
+/// Compass to the closest other players in the same map, e.g. " | Nearby: Ann ↗ 12".
+/// Empty when nobody else is around.
+fn nearby_players_text(app: &App) -> String {
+    let mut nearby: Vec<_> = app.other_players.values()
+        .filter(|p| p.current_map_type == app.current_map_type)
+        .map(|p| {
+            let (dx, dy) = (p.x - app.player.x, p.y - app.player.y);
+            (dx.abs().max(dy.abs()), direction_arrow(dx, dy), p.name.as_str())
+        })
+        .collect();
+
+    if nearby.is_empty() {
+        return String::new();
+    }
+
+    nearby.sort_by_key(|(distance, _, _)| *distance);
+    let entries: Vec<String> = nearby.iter()
+        .take(2)
+        .map(|(distance, arrow, name)| format!("{} {} {}", name, arrow, distance))
+        .collect();
+    format!(" | Nearby: {}", entries.join(", "))
+}
+
+/// Arrow pointing along an offset, snapped to the 8 compass directions
+fn direction_arrow(dx: i32, dy: i32) -> char {
+    if dx == 0 && dy == 0 {
+        return '•';
+    }
+
+    // Screen y grows downwards, so flip it to get a compass angle
+    let angle = (-dy as f64).atan2(dx as f64).to_degrees();
+    let sector = ((angle + 360.0 + 22.5) / 45.0) as usize % 8;
+    ['→', '↗', '↑', '↖', '←', '↙', '↓', '↘'][sector]
+}
+
+/// Color and age (0 = most recent) of the newest trail step left by another player on this map
+fn trail_step_at(app: &App, world_x: i32, world_y: i32) -> Option<((u8, u8, u8), usize)> {
+    app.player_trails.iter()
+        .filter_map(|(id, trail)| {
+            let player = app.other_players.get(id)?;
+            if player.current_map_type != app.current_map_type {
+                return None;
+            }
+            let age = trail.iter().position(|&pos| pos == (world_x, world_y))?;
+            Some((player.color, age))
+        })
+        .min_by_key(|&(_, age)| age)
+}
+
+/// Darken a player color by how old a trail step is, so older steps fade out
+fn faded_color((r, g, b): (u8, u8, u8), age: usize, length: usize) -> Color {
+    let brightness = (length - age.min(length)) as f32 / (length + 1) as f32;
+    scale_rgb((r, g, b), brightness)
+}
+
+/// Scale a color towards black; 0.0 is black and 1.0 leaves it unchanged
+fn scale_rgb((r, g, b): (u8, u8, u8), brightness: f32) -> Color {
+    let channel = |value: u8| (value as f32 * brightness) as u8;
+    Color::Rgb(channel(r), channel(g), channel(b))
+}
+
+/// Scale a style's colors towards black; 0.0 is black and 1.0 leaves it unchanged
+fn apply_brightness_to_style(style: Style, brightness: f32) -> Style {
+    let scale = |color: Color| scale_rgb(color_to_rgb(color), brightness);
+    Style {
+        // Unstyled text uses the terminal's default, assumed to be light on dark
+        fg: Some(scale(style.fg.unwrap_or(Color::Gray))),
+        bg: style.bg.map(scale),
+        ..style
+    }
+}
+
+/// Approximate RGB for the named terminal colors used on the map
+fn color_to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Black => (0, 0, 0),
+        Color::Red => (205, 0, 0),
+        Color::Green => (0, 205, 0),
+        Color::Yellow => (205, 205, 0),
+        Color::Blue => (0, 0, 238),
+        Color::Magenta => (205, 0, 205),
+        Color::Cyan => (0, 205, 205),
+        Color::Gray => (229, 229, 229),
+        Color::DarkGray => (127, 127, 127),
+        Color::LightRed => (255, 0, 0),
+        Color::LightGreen => (0, 255, 0),
+        Color::LightYellow => (255, 255, 0),
+        Color::LightBlue => (92, 92, 255),
+        Color::LightMagenta => (255, 0, 255),
+        Color::LightCyan => (0, 255, 255),
+        _ => (255, 255, 255),
+    }
+}
+
+/// Text for one tile spanning `cell_width` columns. Terrain repeats its glyph
+/// to fill the cells, while characters are padded so they stay readable.
+fn zoomed_glyph(character: char, repeat: bool, cell_width: i32) -> String {
+    let filler = if repeat { character } else { ' ' };
+    let mut text = String::with_capacity(cell_width as usize);
+    text.push(character);
+    for _ in 1..cell_width {
+        text.push(filler);
+    }
+    text
+}
+
 fn get_tile_style_and_char(tile: Tile) -> (Style, char) {
     match tile {
         Tile::Floor => (
@@ -541,7 +959,7 @@ fn get_tile_style_and_char(tile: Tile) -> (Style, char) {
             '#'
         ),
         Tile::Empty => (
-            Style::default(),
+            Style::default().bg(Color::Black), // Same as unloaded void
             ' '
         ),
         Tile::Door => (
@@ -576,20 +994,43 @@ fn get_tile_style_and_char(tile: Tile) -> (Style, char) {
             Style::default().fg(Color::Red).bg(Color::Black),
             'D'
         ),
+        Tile::Tower => (
+            Style::default().fg(Color::White).bg(Color::Rgb(72, 61, 139)), // Dark slate blue background
+            'I'
+        ),
+        Tile::Ruins => (
+            Style::default().fg(Color::Gray).bg(Color::Rgb(85, 85, 85)),
+            '%'
+        ),
         Tile::DungeonExit => (
             Style::default().fg(Color::Cyan).bg(Color::Black),
             '<'
         ),
+        Tile::DungeonShortcut => (
+            Style::default().fg(Color::Green).bg(Color::Black),
+            '<'
+        ),
     }
 }
 
-fn render_inventory(frame: &mut Frame, _app: &App, area: Rect) {
+fn render_inventory(frame: &mut Frame, app: &App, area: Rect) {
     let inventory_block = Block::default()
         .borders(Borders::ALL)
         .title("Inventory")
         .style(Style::default());
 
-    let inventory_text = "Your inventory is empty.\n\nPress 'g' to return to game.";
+    let inventory_text = if app.player.inventory.is_empty() {
+        "Your inventory is empty.\n\nPress 'g' to return to game.".to_string()
+    } else {
+        let mut text: String = Item::stack(&app.player.inventory).iter()
+            .map(|(item, count)| match count {
+                1 => format!("- {}\n", item.name()),
+                _ => format!("- {}x {}\n", count, item.name()),
+            })
+            .collect();
+        text.push_str("\nPress 'e' to eat, 'r' to read a recall scroll, 's' to look through a spyglass, 'g' to return to game.");
+        text
+    };
     
     let inventory = Paragraph::new(Text::styled(
         inventory_text,
@@ -600,7 +1041,119 @@ fn render_inventory(frame: &mut Frame, _app: &App, area: Rect) {
     frame.render_widget(inventory, area);
 }
 
-fn render_exit_screen(frame: &mut Frame, _app: &App, area: Rect) {
+fn render_fast_travel(frame: &mut Frame, app: &App, area: Rect) {
+    let mut village_items = Vec::<ListItem>::new();
+    for (i, (x, y)) in app.visited_villages.iter().enumerate() {
+        let selected = i == app.fast_travel_selected;
+        let style = if selected {
+            Style::default().fg(Color::Yellow).bg(Color::DarkGray)
+        } else {
+            Style::default().fg(Color::White)
+        };
+
+        let prefix = if selected { "▶ " } else { "  " };
+        village_items.push(ListItem::new(Line::from(Span::styled(
+            format!("{}Village at ({}, {})", prefix, x, y),
+            style,
+        ))));
+    }
+
+    let village_list = List::new(village_items)
+        .block(Block::default().borders(Borders::ALL).title("Fast Travel (↑/↓ to select, Enter to travel, Esc to cancel)"));
+
+    frame.render_widget(village_list, area);
+}
+
+const fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+const fn shift(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::SHIFT)
+}
+
+const fn ch(c: char) -> KeyEvent {
+    key(KeyCode::Char(c))
+}
+
+/// Game controls listed on the help screen: the keys as shown, what they do
+/// and every key the row covers, which a test holds to the game's key bindings
+pub const CONTROLS: &[(&str, &str, &[KeyEvent])] = &[
+    ("h/j/k/l, arrows", "Move", &[
+        ch('h'), ch('j'), ch('k'), ch('l'),
+        key(KeyCode::Left), key(KeyCode::Down), key(KeyCode::Up), key(KeyCode::Right),
+    ]),
+    ("y/u/b/n", "Move diagonally", &[ch('y'), ch('u'), ch('b'), ch('n')]),
+    ("Shift + direction", "Sprint", &[
+        ch('H'), ch('J'), ch('K'), ch('L'),
+        ch('Y'), ch('U'), ch('B'), ch('N'),
+        shift(KeyCode::Left), shift(KeyCode::Down), shift(KeyCode::Up), shift(KeyCode::Right),
+    ]),
+    (".", "Wait a turn", &[ch('.')]),
+    ("e", "Enter dungeon", &[ch('e')]),
+    ("x", "Exit dungeon", &[ch('x')]),
+    ("f", "Chop tree", &[ch('f')]),
+    ("r", "Lay road", &[ch('r')]),
+    ("t", "Fast travel", &[ch('t')]),
+    ("z", "Cycle zoom", &[ch('z')]),
+    ("m", "Dungeon minimap", &[ch('m')]),
+    ("o", "Filter message log", &[ch('o')]),
+    ("p", "Player name tags", &[ch('p')]),
+    ("i", "Inventory (e eat, r recall, s spyglass)", &[ch('i')]),
+    ("c", "Chat (multiplayer)", &[ch('c')]),
+    ("PgUp/PgDn", "Scroll back through chat", &[key(KeyCode::PageUp), key(KeyCode::PageDown)]),
+    ("V", "Reveal the whole dungeon (debug)", &[ch('V')]),
+    ("G", "Coordinate grid (debug)", &[ch('G')]),
+    ("?", "Toggle this help", &[ch('?')]),
+    ("q", "Quit", &[ch('q')]),
+];
+
+fn render_help(frame: &mut Frame, area: Rect) {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    // Built from the same styles the map uses so the legend can't drift
+    let mut legend_items: Vec<ListItem> = Tile::ALL.iter()
+        .map(|&tile| {
+            let (style, character) = get_tile_style_and_char(tile);
+            ListItem::new(Line::from(vec![
+                Span::styled(character.to_string(), style),
+                Span::raw(format!("  {}", tile.name())),
+            ]))
+        })
+        .collect();
+    legend_items.push(ListItem::new(Line::from(vec![
+        Span::styled("░", Style::default().fg(Color::DarkGray).bg(Color::Black)),
+        Span::raw("  Beyond the dungeon edge"),
+    ])));
+    legend_items.push(ListItem::new(Line::from(vec![
+        Span::styled("@", Style::default().fg(Color::Yellow)),
+        Span::raw("  You"),
+    ])));
+    legend_items.push(ListItem::new(Line::from(vec![
+        Span::styled("@", Style::default().fg(Color::Cyan)),
+        Span::raw("  Other players"),
+    ])));
+
+    let legend = List::new(legend_items)
+        .block(Block::default().borders(Borders::ALL).title("Legend"));
+    frame.render_widget(legend, columns[0]);
+
+    let control_items: Vec<ListItem> = CONTROLS.iter()
+        .map(|(keys, action, _)| ListItem::new(Line::from(vec![
+            Span::styled(format!("{:<16}", keys), Style::default().fg(Color::Yellow)),
+            Span::raw(*action),
+        ])))
+        .collect();
+
+    let controls = List::new(control_items)
+        .block(Block::default().borders(Borders::ALL).title("Controls (? or Esc to close)"));
+    frame.render_widget(controls, columns[1]);
+}
+
+fn render_exit_screen(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Clear, area);
     
     let popup_block = Block::default()
@@ -608,10 +1161,17 @@ fn render_exit_screen(frame: &mut Frame, _app: &App, area: Rect) {
         .borders(Borders::ALL)
         .style(Style::default().bg(Color::DarkGray));
 
-    let exit_text = Text::styled(
-        "Are you sure you want to quit? (y/n)",
-        Style::default().fg(Color::Red),
-    );
+    let exit_text = if app.has_unsaved_changes() {
+        Text::styled(
+            "The world has changed since it was last saved.\n\n(s) save and quit, (y) quit without saving, (n) cancel",
+            Style::default().fg(Color::Red),
+        )
+    } else {
+        Text::styled(
+            "Are you sure you want to quit? (y/n)",
+            Style::default().fg(Color::Red),
+        )
+    };
     
     let exit_paragraph = Paragraph::new(exit_text)
         .block(popup_block)
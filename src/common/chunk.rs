@@ -1,4 +1,8 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use noise::{NoiseFn, Perlin};
 use serde::{Serialize, Deserialize};
 use super::terrain::Tile;
@@ -6,12 +10,137 @@ use super::terrain::Tile;
 /// Size of each chunk in tiles
 pub const CHUNK_SIZE: i32 = 32;
 
-/// Radius of chunks to keep loaded around the player
+/// Default radius of chunks to keep loaded around the player
 pub const CHUNK_LOAD_RADIUS: i32 = 3;
 
-/// Maximum number of chunks to keep in memory
+/// Default maximum number of chunks to keep in memory
 pub const MAX_LOADED_CHUNKS: usize = 64;
 
+/// Default time after which a chunk nobody looked at is unloaded
+pub const CHUNK_UNLOAD_TIMEOUT_SECS: u64 = 300;
+
+/// Runtime limits for how much of the world a chunk manager keeps loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkSettings {
+    pub load_radius: i32,          // Chunks loaded in every direction around the player
+    pub max_loaded_chunks: usize,  // Eviction threshold for loaded chunks
+    pub unload_timeout_secs: u64,  // Chunks not accessed for this long are unloaded
+}
+
+impl Default for ChunkSettings {
+    fn default() -> Self {
+        ChunkSettings {
+            load_radius: CHUNK_LOAD_RADIUS,
+            max_loaded_chunks: MAX_LOADED_CHUNKS,
+            unload_timeout_secs: CHUNK_UNLOAD_TIMEOUT_SECS,
+        }
+    }
+}
+
+impl ChunkSettings {
+    /// Number of chunks in the square loaded around the player
+    pub fn chunks_in_radius(&self) -> usize {
+        let side = (2 * self.load_radius + 1) as usize;
+        side * side
+    }
+
+    /// Shortest unload timeout for this radius: a second per tile from the
+    /// player to the edge of the loaded area, so chunks loaded ahead are still
+    /// there when the player walks into them
+    pub fn min_unload_timeout_secs(&self) -> u64 {
+        (self.load_radius.max(0) * CHUNK_SIZE) as u64
+    }
+
+    /// Make sure the loaded area fits under the eviction threshold and lives long
+    /// enough, otherwise chunks loaded around the player would be evicted too soon
+    pub fn validate(&self) -> Result<(), String> {
+        if self.load_radius < 0 {
+            return Err("Chunk load radius can't be negative".to_string());
+        }
+        if self.chunks_in_radius() > self.max_loaded_chunks {
+            return Err(format!(
+                "Chunk load radius {} needs {} chunks, above the limit of {} loaded chunks",
+                self.load_radius,
+                self.chunks_in_radius(),
+                self.max_loaded_chunks
+            ));
+        }
+        if self.unload_timeout_secs < self.min_unload_timeout_secs() {
+            return Err(format!(
+                "Chunk unload timeout {}s is too short for load radius {}; use at least {}s",
+                self.unload_timeout_secs,
+                self.load_radius,
+                self.min_unload_timeout_secs()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Thresholds that pick the overworld terrain from the elevation, moisture,
+/// temperature and detail noise, all between 0 and 1. The defaults make the
+/// standard world; changing them reshapes every chunk generated afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BiomeConfig {
+    pub mountain_elevation: f64,     // Above this is always mountains
+    pub highland_elevation: f64,     // Above this are hills: forest if moist, mountains otherwise
+    pub lowland_elevation: f64,      // Below this is lowland: grass, or wetland water if very moist
+    pub lake_elevation: f64,         // Below this, moist ground becomes lakes
+    pub lake_moisture: f64,
+    pub cold_temperature: f64,       // Hills colder than this are mountains
+    pub hill_forest_moisture: f64,   // Moisture for forested hills
+    pub forest_moisture: f64,        // Moisture for forest between lowland and highland
+    pub dense_forest_detail: f64,    // Detail noise above which that forest has trees
+    pub wetland_moisture: f64,       // Moisture for lowland water
+}
+
+impl Default for BiomeConfig {
+    fn default() -> Self {
+        BiomeConfig {
+            mountain_elevation: 0.8,
+            highland_elevation: 0.6,
+            lowland_elevation: 0.4,
+            lake_elevation: 0.2,
+            lake_moisture: 0.6,
+            cold_temperature: 0.3,
+            hill_forest_moisture: 0.5,
+            forest_moisture: 0.6,
+            dense_forest_detail: 0.7,
+            wetland_moisture: 0.7,
+        }
+    }
+}
+
+impl BiomeConfig {
+    /// Every threshold has to lie within the 0 to 1 range of the noise
+    pub fn validate(&self) -> Result<(), String> {
+        let thresholds = [
+            ("mountain_elevation", self.mountain_elevation),
+            ("highland_elevation", self.highland_elevation),
+            ("lowland_elevation", self.lowland_elevation),
+            ("lake_elevation", self.lake_elevation),
+            ("lake_moisture", self.lake_moisture),
+            ("cold_temperature", self.cold_temperature),
+            ("hill_forest_moisture", self.hill_forest_moisture),
+            ("forest_moisture", self.forest_moisture),
+            ("dense_forest_detail", self.dense_forest_detail),
+            ("wetland_moisture", self.wetland_moisture),
+        ];
+        match thresholds.iter().find(|(_, value)| !(0.0..=1.0).contains(value)) {
+            Some((name, value)) => Err(format!("Biome threshold {} must be between 0 and 1, got {}", name, value)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Side length of the square regions that each hold at most one landmark
+pub const LANDMARK_REGION_SIZE: i32 = 160;
+
+/// Distance from a village's center to its surrounding wall
+pub const VILLAGE_RADIUS: i32 = 6;
+
 /// Represents a 2D coordinate for a chunk
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ChunkCoord {
@@ -63,15 +192,25 @@ pub struct Chunk {
     pub coord: ChunkCoord,
     pub tiles: HashMap<(i32, i32), Tile>, // Local coordinates within chunk (0..CHUNK_SIZE)
     pub generated: bool,
+    pub dirty: bool, // Modified since generation/load and not yet written to disk
     pub last_accessed: std::time::Instant,
 }
 
+/// On-disk representation of a chunk, tiles stored row-major (y * CHUNK_SIZE + x)
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedChunk {
+    chunk_x: i32,
+    chunk_y: i32,
+    tiles: Vec<Tile>,
+}
+
 impl Chunk {
     pub fn new(coord: ChunkCoord) -> Self {
         Chunk {
             coord,
             tiles: HashMap::new(),
             generated: false,
+            dirty: false,
             last_accessed: std::time::Instant::now(),
         }
     }
@@ -82,10 +221,12 @@ impl Chunk {
         self.tiles.get(&(local_x, local_y)).copied()
     }
 
-    /// Set a tile at local chunk coordinates
+    /// Set a tile at local chunk coordinates, marking the chunk dirty if it changed
     pub fn set_tile(&mut self, local_x: i32, local_y: i32, tile: Tile) {
         self.last_accessed = std::time::Instant::now();
-        self.tiles.insert((local_x, local_y), tile);
+        if self.tiles.insert((local_x, local_y), tile) != Some(tile) {
+            self.dirty = true;
+        }
     }
 
     /// Convert world coordinates to local chunk coordinates
@@ -117,6 +258,56 @@ impl Chunk {
         self.generated = true;
         self.last_accessed = std::time::Instant::now();
     }
+
+    /// File name used for this chunk inside a world directory
+    fn file_name(coord: ChunkCoord) -> String {
+        format!("chunk_{}_{}.json", coord.x, coord.y)
+    }
+
+    /// Write the chunk to the world directory and clear its dirty flag
+    pub fn save_to_dir(&mut self, world_dir: &Path) -> io::Result<()> {
+        let mut tiles = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
+        for local_y in 0..CHUNK_SIZE {
+            for local_x in 0..CHUNK_SIZE {
+                tiles.push(self.tiles.get(&(local_x, local_y)).copied().unwrap_or(Tile::Empty));
+            }
+        }
+
+        let saved = SavedChunk {
+            chunk_x: self.coord.x,
+            chunk_y: self.coord.y,
+            tiles,
+        };
+        let json = serde_json::to_string(&saved)?;
+
+        fs::create_dir_all(world_dir)?;
+        fs::write(world_dir.join(Self::file_name(self.coord)), json)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Load a previously saved chunk from the world directory, if one exists
+    pub fn load_from_dir(world_dir: &Path, coord: ChunkCoord) -> io::Result<Option<Chunk>> {
+        let path = world_dir.join(Self::file_name(coord));
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = fs::read_to_string(path)?;
+        let saved: SavedChunk = serde_json::from_str(&json)?;
+        if saved.chunk_x != coord.x || saved.chunk_y != coord.y
+            || saved.tiles.len() != (CHUNK_SIZE * CHUNK_SIZE) as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "chunk file does not match its coordinates"));
+        }
+
+        let mut chunk = Chunk::new(coord);
+        for (index, tile) in saved.tiles.into_iter().enumerate() {
+            let index = index as i32;
+            chunk.tiles.insert((index % CHUNK_SIZE, index / CHUNK_SIZE), tile);
+        }
+        chunk.generated = true;
+        Ok(Some(chunk))
+    }
 }
 
 /// Manages infinite terrain generation using a chunking system
@@ -127,16 +318,22 @@ pub struct InfiniteTerrainGenerator {
     temperature_noise: Perlin,
     feature_noise: Perlin,
     seed: u32,
+    biomes: BiomeConfig,
 }
 
 impl InfiniteTerrainGenerator {
     pub fn new(seed: u32) -> Self {
+        Self::with_biomes(seed, BiomeConfig::default())
+    }
+
+    pub fn with_biomes(seed: u32, biomes: BiomeConfig) -> Self {
         InfiniteTerrainGenerator {
             elevation_noise: Perlin::new(seed),
             moisture_noise: Perlin::new(seed.wrapping_add(1000)),
             temperature_noise: Perlin::new(seed.wrapping_add(2000)),
             feature_noise: Perlin::new(seed.wrapping_add(4000)),
             seed,
+            biomes,
         }
     }
 
@@ -158,14 +355,18 @@ impl InfiniteTerrainGenerator {
         let detail = self.sample_detail(detail_x, detail_y);
 
         // Generate special features
-        if self.should_place_village(world_x, world_y) {
-            return Tile::Village;
+        if let Some(tile) = self.village_tile_at(world_x, world_y) {
+            return tile;
         }
 
         if self.should_place_dungeon_entrance(world_x, world_y) {
             return Tile::DungeonEntrance;
         }
 
+        if let Some(landmark) = self.landmark_at(world_x, world_y) {
+            return landmark;
+        }
+
         // Generate roads
         if self.should_place_road(world_x, world_y) {
             return Tile::Road;
@@ -213,13 +414,85 @@ impl InfiniteTerrainGenerator {
     fn should_place_village(&self, world_x: i32, world_y: i32) -> bool {
         // Villages appear at specific pseudo-random locations
         let hash = self.hash_coords(world_x, world_y, 12345);
-        hash % 10000 == 0 && self.is_suitable_for_village(world_x, world_y)
+        hash.is_multiple_of(10000) && self.is_suitable_for_village(world_x, world_y)
+    }
+
+    /// Tile of the walled village covering this position, if any. The center keeps
+    /// the `Village` marker; around it are streets, a few houses and a wall with four gates.
+    fn village_tile_at(&self, world_x: i32, world_y: i32) -> Option<Tile> {
+        let (center_x, center_y) = (-VILLAGE_RADIUS..=VILLAGE_RADIUS)
+            .flat_map(|dy| (-VILLAGE_RADIUS..=VILLAGE_RADIUS).map(move |dx| (world_x + dx, world_y + dy)))
+            .find(|&(x, y)| self.should_place_village(x, y))?;
+        let (local_x, local_y) = (world_x - center_x, world_y - center_y);
+
+        if local_x == 0 && local_y == 0 {
+            return Some(Tile::Village);
+        }
+
+        // Outer wall, with a gate in the middle of each side
+        if local_x.abs().max(local_y.abs()) == VILLAGE_RADIUS {
+            return Some(if local_x == 0 || local_y == 0 { Tile::Door } else { Tile::Wall });
+        }
+
+        // The streets along the axes stay open; each quadrant may hold a house
+        if local_x == 0 || local_y == 0 {
+            return Some(Tile::Floor);
+        }
+        let quadrant = (local_x > 0) as u32 + 2 * (local_y > 0) as u32;
+        let bits = self.hash_coords(center_x, center_y, 13579) >> (quadrant * 8);
+        if bits & 0b11 == 0 {
+            return Some(Tile::Floor); // No house here
+        }
+
+        let width = 3 + ((bits >> 2) & 1) as i32;
+        let height = 3 + ((bits >> 3) & 1) as i32;
+        let offset_x = 1 + ((bits >> 4) & 0b11) as i32 % (VILLAGE_RADIUS - width);
+        let offset_y = 1 + ((bits >> 6) & 0b11) as i32 % (VILLAGE_RADIUS - height);
+        let (house_x, house_y) = (local_x.abs() - offset_x, local_y.abs() - offset_y);
+
+        if house_x < 0 || house_y < 0 || house_x >= width || house_y >= height {
+            Some(Tile::Floor)
+        } else if house_x == 0 && house_y == height / 2 {
+            Some(Tile::Door) // Facing the street through the middle of the village
+        } else if house_x == 0 || house_y == 0 || house_x == width - 1 || house_y == height - 1 {
+            Some(Tile::Wall)
+        } else {
+            Some(Tile::Floor)
+        }
     }
 
     fn should_place_dungeon_entrance(&self, world_x: i32, world_y: i32) -> bool {
         // Dungeon entrances are more common and accessible
         let hash = self.hash_coords(world_x, world_y, 54321);
-        hash % 8000 == 0 && self.is_suitable_for_dungeon(world_x, world_y)
+        hash.is_multiple_of(8000) && self.is_suitable_for_dungeon(world_x, world_y)
+    }
+
+    fn landmark_at(&self, world_x: i32, world_y: i32) -> Option<Tile> {
+        // Each region gets at most one landmark at a hashed spot away from its edges,
+        // which keeps landmarks rare and well spaced
+        let region_x = world_x.div_euclid(LANDMARK_REGION_SIZE);
+        let region_y = world_y.div_euclid(LANDMARK_REGION_SIZE);
+        let hash = self.hash_coords(region_x, region_y, 24680);
+
+        // Only about a third of regions have one
+        if !hash.is_multiple_of(3) {
+            return None;
+        }
+
+        let margin = LANDMARK_REGION_SIZE / 4;
+        let span = (LANDMARK_REGION_SIZE - 2 * margin) as u32;
+        let landmark_x = region_x * LANDMARK_REGION_SIZE + margin + ((hash >> 4) % span) as i32;
+        let landmark_y = region_y * LANDMARK_REGION_SIZE + margin + ((hash >> 16) % span) as i32;
+
+        if world_x != landmark_x || world_y != landmark_y || !self.is_suitable_for_landmark(world_x, world_y) {
+            return None;
+        }
+
+        if hash & 0b1000 == 0 {
+            Some(Tile::Tower)
+        } else {
+            Some(Tile::Ruins)
+        }
     }
 
     fn should_place_road(&self, world_x: i32, world_y: i32) -> bool {
@@ -267,6 +540,13 @@ impl InfiniteTerrainGenerator {
         elevation > 0.3 && elevation < 0.7 && moisture > 0.4
     }
 
+    fn is_suitable_for_landmark(&self, world_x: i32, world_y: i32) -> bool {
+        let elevation = self.sample_elevation(world_x as f64 * 0.02, world_y as f64 * 0.02);
+
+        // Anywhere dry and below the mountain line
+        elevation > 0.25 && elevation < 0.8
+    }
+
     fn is_suitable_for_dungeon(&self, world_x: i32, world_y: i32) -> bool {
         let elevation = self.sample_elevation(world_x as f64 * 0.02, world_y as f64 * 0.02);
         let moisture = self.sample_moisture(world_x as f64 * 0.014, world_y as f64 * 0.014);
@@ -277,48 +557,46 @@ impl InfiniteTerrainGenerator {
     }
 
     fn determine_biome_tile(&self, elevation: f64, moisture: f64, temperature: f64, detail: f64) -> Tile {
+        let biomes = &self.biomes;
+
         // High elevation = mountains
-        if elevation > 0.8 {
+        if elevation > biomes.mountain_elevation {
             return Tile::Mountain;
         }
 
         // Very low elevation with high moisture = water
-        if elevation < 0.2 && moisture > 0.6 {
+        if elevation < biomes.lake_elevation && moisture > biomes.lake_moisture {
             return Tile::Water;
         }
 
         // Medium-high elevation
-        if elevation > 0.6 {
-            if temperature < 0.3 {
+        if elevation > biomes.highland_elevation {
+            if temperature < biomes.cold_temperature {
                 Tile::Mountain // Cold mountains
-            } else if moisture > 0.5 {
+            } else if moisture > biomes.hill_forest_moisture {
                 Tile::Tree // Forested hills
             } else {
                 Tile::Mountain // Dry hills
             }
         }
         // Medium elevation
-        else if elevation > 0.4 {
-            if moisture > 0.6 {
-                if detail > 0.7 {
+        else if elevation > biomes.lowland_elevation {
+            if moisture > biomes.forest_moisture {
+                if detail > biomes.dense_forest_detail {
                     Tile::Tree // Dense forest
                 } else {
                     Tile::Grass // Forest edge
                 }
-            } else if moisture > 0.3 {
-                Tile::Grass // Plains
             } else {
-                Tile::Grass // Dry grassland
+                Tile::Grass // Plains and dry grassland
             }
         }
         // Low elevation
         else {
-            if moisture > 0.7 {
+            if moisture > biomes.wetland_moisture {
                 Tile::Water // Wetlands
-            } else if moisture > 0.4 {
-                Tile::Grass // Wet grasslands
             } else {
-                Tile::Grass // Dry lowlands
+                Tile::Grass // Wet grasslands and dry lowlands
             }
         }
     }
@@ -330,6 +608,55 @@ impl InfiniteTerrainGenerator {
         hash = hash.wrapping_add(salt).wrapping_mul(17);
         hash
     }
+
+    /// Count every tile in the square of `radius` tiles around a point, for
+    /// checking how a seed and biome config shape the world
+    pub fn sample_biomes(&self, center_x: i32, center_y: i32, radius: i32) -> BiomeStats {
+        let mut counts = Tile::ALL.map(|tile| (tile, 0));
+        for y in center_y - radius..=center_y + radius {
+            for x in center_x - radius..=center_x + radius {
+                let tile = self.generate_tile_at(x, y);
+                if let Some((_, count)) = counts.iter_mut().find(|(counted, _)| *counted == tile) {
+                    *count += 1;
+                }
+            }
+        }
+        let side = (2 * radius + 1) as usize;
+        BiomeStats { tiles_sampled: side * side, counts: counts.to_vec() }
+    }
+}
+
+/// How often each tile came up in a sampled area of the overworld
+#[derive(Debug, Clone, PartialEq)]
+pub struct BiomeStats {
+    pub tiles_sampled: usize,
+    pub counts: Vec<(Tile, usize)>, // Every tile kind, in `Tile::ALL` order
+}
+
+impl BiomeStats {
+    pub fn count(&self, tile: Tile) -> usize {
+        self.counts.iter().find(|(counted, _)| *counted == tile).map_or(0, |(_, count)| *count)
+    }
+
+    pub fn percent(&self, tile: Tile) -> f64 {
+        self.count(tile) as f64 * 100.0 / self.tiles_sampled.max(1) as f64
+    }
+
+    /// Occurrences per 1000 tiles, for features too rare to show as a percentage
+    pub fn per_thousand(&self, tile: Tile) -> f64 {
+        self.percent(tile) * 10.0
+    }
+}
+
+impl fmt::Display for BiomeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} tiles sampled", self.tiles_sampled)?;
+        for &(tile, count) in self.counts.iter().filter(|(_, count)| *count > 0) {
+            writeln!(f, "  {:<16} {:>6.2}%  ({})", tile.name(), self.percent(tile), count)?;
+        }
+        writeln!(f, "Villages per 1000 tiles: {:.3}", self.per_thousand(Tile::Village))?;
+        write!(f, "Dungeons per 1000 tiles: {:.3}", self.per_thousand(Tile::DungeonEntrance))
+    }
 }
 
 /// Manages loaded chunks and provides infinite terrain
@@ -338,17 +665,61 @@ pub struct ChunkManager {
     chunks: HashMap<ChunkCoord, Chunk>,
     terrain_generator: InfiniteTerrainGenerator,
     player_chunk: ChunkCoord,
+    settings: ChunkSettings,
+    world_dir: Option<PathBuf>, // Where modified chunks are persisted, if enabled
+    manual_saves: bool, // Modified chunks stay loaded until save_dirty_chunks instead of saving on eviction
+    chunks_generated: u64, // Chunks built from the seed rather than loaded from disk
 }
 
 impl ChunkManager {
-    pub fn new(seed: u32) -> Self {
+    pub fn new(seed: u32, settings: ChunkSettings) -> Self {
         ChunkManager {
             chunks: HashMap::new(),
             terrain_generator: InfiniteTerrainGenerator::new(seed),
             player_chunk: ChunkCoord::new(0, 0),
+            settings,
+            world_dir: None,
+            manual_saves: false,
+            chunks_generated: 0,
         }
     }
 
+    /// Generate terrain with other biome thresholds from now on; chunks already
+    /// loaded or saved keep their terrain
+    pub fn set_biomes(&mut self, biomes: BiomeConfig) {
+        self.terrain_generator = InfiniteTerrainGenerator::with_biomes(self.terrain_generator.seed, biomes);
+    }
+
+    /// Create a chunk manager that saves modified chunks to `world_dir` before
+    /// evicting them and reloads them from there instead of regenerating
+    pub fn with_world_dir(seed: u32, settings: ChunkSettings, world_dir: impl Into<PathBuf>) -> Self {
+        let mut manager = Self::new(seed, settings);
+        manager.world_dir = Some(world_dir.into());
+        manager
+    }
+
+    /// Only write modified chunks when `save_dirty_chunks` is called. They are
+    /// kept loaded until then rather than saved on eviction, so unsaved changes
+    /// can still be thrown away by dropping the manager.
+    pub fn set_manual_saves(&mut self, manual_saves: bool) {
+        self.manual_saves = manual_saves;
+    }
+
+    /// Whether any loaded chunk has changes that aren't on disk yet
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.world_dir.is_some() && self.chunks.values().any(|chunk| chunk.dirty)
+    }
+
+    /// How many chunks have been generated from the seed since this manager was created
+    pub fn chunks_generated(&self) -> u64 {
+        self.chunks_generated
+    }
+
+    /// Directory modified chunks are persisted to, if persistence is enabled
+    pub fn world_dir(&self) -> Option<&Path> {
+        self.world_dir.as_deref()
+    }
+
     /// Update the player's position and manage chunk loading/unloading
     pub fn update_player_position(&mut self, player_x: i32, player_y: i32) {
         let new_player_chunk = ChunkCoord::from_world_pos(player_x, player_y);
@@ -410,16 +781,67 @@ impl ChunkManager {
         tiles
     }
 
+    /// Write every modified chunk to the world directory (e.g. before shutting down)
+    pub fn save_dirty_chunks(&mut self) -> io::Result<()> {
+        let Some(world_dir) = self.world_dir.as_deref() else {
+            return Ok(());
+        };
+
+        for chunk in self.chunks.values_mut().filter(|chunk| chunk.dirty) {
+            chunk.save_to_dir(world_dir)?;
+        }
+        Ok(())
+    }
+
     fn ensure_chunk_loaded(&mut self, chunk_coord: ChunkCoord) {
         if !self.chunks.contains_key(&chunk_coord) {
-            let mut chunk = Chunk::new(chunk_coord);
-            chunk.generate(&self.terrain_generator);
+            // Prefer a persisted copy so player modifications survive eviction;
+            // an unreadable file falls back to regenerating from the seed
+            let saved_chunk = self.world_dir.as_deref()
+                .and_then(|world_dir| match Chunk::load_from_dir(world_dir, chunk_coord) {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        log::warn!("Regenerating chunk ({}, {}), failed to load its save: {}", chunk_coord.x, chunk_coord.y, e);
+                        None
+                    }
+                });
+
+            let chunk = saved_chunk.unwrap_or_else(|| {
+                log::debug!("Generating chunk ({}, {})", chunk_coord.x, chunk_coord.y);
+                self.chunks_generated += 1;
+                let mut chunk = Chunk::new(chunk_coord);
+                chunk.generate(&self.terrain_generator);
+                chunk
+            });
             self.chunks.insert(chunk_coord, chunk);
         }
     }
 
+    /// Whether a chunk can be dropped from memory without losing changes that
+    /// are only written on request
+    fn is_evictable(&self, chunk: &Chunk) -> bool {
+        !(self.manual_saves && self.world_dir.is_some() && chunk.dirty)
+    }
+
+    /// Remove a chunk from memory, persisting it first if it has unsaved changes.
+    /// A chunk that fails to save stays loaded so its modifications aren't lost.
+    fn evict_chunk(&mut self, coord: ChunkCoord) -> bool {
+        if self.chunks.get(&coord).is_some_and(|chunk| !self.is_evictable(chunk)) {
+            return false;
+        }
+        if let (Some(world_dir), Some(chunk)) = (self.world_dir.as_deref(), self.chunks.get_mut(&coord)) {
+            if chunk.dirty {
+                if let Err(e) = chunk.save_to_dir(world_dir) {
+                    log::warn!("Keeping chunk ({}, {}) loaded, failed to save it: {}", coord.x, coord.y, e);
+                    return false;
+                }
+            }
+        }
+        self.chunks.remove(&coord).is_some()
+    }
+
     fn load_chunks_around_player(&mut self) {
-        let chunks_to_load = self.player_chunk.neighbors_within_radius(CHUNK_LOAD_RADIUS);
+        let chunks_to_load = self.player_chunk.neighbors_within_radius(self.settings.load_radius);
         
         for chunk_coord in chunks_to_load {
             self.ensure_chunk_loaded(chunk_coord);
@@ -432,27 +854,279 @@ impl ChunkManager {
             .iter()
             .filter(|(coord, chunk)| {
                 let distance = self.player_chunk.distance_to(coord);
-                distance > CHUNK_LOAD_RADIUS + 1 || 
-                chunk.last_accessed.elapsed().as_secs() > 300 // 5 minutes
+                distance > self.settings.load_radius + 1 || 
+                chunk.last_accessed.elapsed().as_secs() > self.settings.unload_timeout_secs
             })
             .map(|(coord, _)| *coord)
             .collect();
 
         for coord in chunks_to_remove {
-            self.chunks.remove(&coord);
+            self.evict_chunk(coord);
         }
 
         // If still too many chunks, remove the oldest ones
-        while self.chunks.len() > MAX_LOADED_CHUNKS {
+        while self.chunks.len() > self.settings.max_loaded_chunks {
             if let Some(oldest_coord) = self.chunks
                 .iter()
+                .filter(|(_, chunk)| self.is_evictable(chunk))
                 .min_by_key(|(_, chunk)| chunk.last_accessed)
                 .map(|(coord, _)| *coord)
             {
-                self.chunks.remove(&oldest_coord);
+                if !self.evict_chunk(oldest_coord) {
+                    break;
+                }
             } else {
                 break;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn chunk_center(x: i32, y: i32) -> (i32, i32) {
+        (x * CHUNK_SIZE + CHUNK_SIZE / 2, y * CHUNK_SIZE + CHUNK_SIZE / 2)
+    }
+
+    fn move_to_chunk(manager: &mut ChunkManager, x: i32, y: i32) {
+        let (world_x, world_y) = chunk_center(x, y);
+        manager.update_player_position(world_x, world_y);
+    }
+
+    fn age_chunk(manager: &mut ChunkManager, coord: ChunkCoord, age: Duration) {
+        let chunk = manager.chunks.get_mut(&coord).expect("chunk should be loaded");
+        chunk.last_accessed = Instant::now().checked_sub(age).expect("clock too close to boot time");
+    }
+
+    #[test]
+    fn walking_away_evicts_distant_chunks_and_respects_cap() {
+        let mut manager = ChunkManager::new(12345, ChunkSettings::default());
+
+        for chunk_x in 1..=20 {
+            move_to_chunk(&mut manager, chunk_x, 0);
+            assert!(manager.chunks.len() <= MAX_LOADED_CHUNKS);
+            for coord in manager.chunks.keys() {
+                assert!(manager.player_chunk.distance_to(coord) <= CHUNK_LOAD_RADIUS + 1);
+            }
+        }
+
+        assert!(!manager.chunks.contains_key(&ChunkCoord::new(0, 0)));
+        assert!(manager.chunks.contains_key(&ChunkCoord::new(20, 0)));
+    }
+
+    #[test]
+    fn stale_chunks_time_out_but_recent_ones_survive() {
+        let mut manager = ChunkManager::new(12345, ChunkSettings::default());
+        move_to_chunk(&mut manager, 1, 0);
+
+        let stale = ChunkCoord::new(0, 0);
+        let fresh = ChunkCoord::new(0, 1);
+        age_chunk(&mut manager, stale, Duration::from_secs(301));
+
+        move_to_chunk(&mut manager, 2, 0);
+
+        assert!(!manager.chunks.contains_key(&stale));
+        assert!(manager.chunks.contains_key(&fresh));
+    }
+
+    #[test]
+    fn unload_timeout_is_configurable_but_not_below_the_walk_across_the_radius() {
+        let settings = ChunkSettings { load_radius: 1, max_loaded_chunks: 20, unload_timeout_secs: 60 };
+        assert!(settings.validate().is_ok());
+        let mut manager = ChunkManager::new(12345, settings);
+        move_to_chunk(&mut manager, 1, 0);
+
+        let stale = ChunkCoord::new(1, 1);
+        let fresh = ChunkCoord::new(1, -1);
+        age_chunk(&mut manager, stale, Duration::from_secs(61));
+        age_chunk(&mut manager, fresh, Duration::from_secs(59));
+
+        move_to_chunk(&mut manager, 2, 0);
+
+        assert!(!manager.chunks.contains_key(&stale));
+        assert!(manager.chunks.contains_key(&fresh));
+
+        let too_short = ChunkSettings { unload_timeout_secs: 60, ..ChunkSettings::default() };
+        assert!(too_short.validate().unwrap_err().contains("at least 96s"));
+    }
+
+    #[test]
+    fn cap_evicts_least_recently_accessed_chunks() {
+        let settings = ChunkSettings { load_radius: 1, max_loaded_chunks: 10, ..ChunkSettings::default() };
+        let mut manager = ChunkManager::new(12345, settings);
+        move_to_chunk(&mut manager, 1, 0);
+        assert_eq!(manager.chunks.len(), 9);
+
+        // Every chunk in column 0 stays within the distance threshold after the next move,
+        // so only the cap decides which of them go
+        age_chunk(&mut manager, ChunkCoord::new(0, -1), Duration::from_secs(20));
+        age_chunk(&mut manager, ChunkCoord::new(0, 1), Duration::from_secs(10));
+
+        move_to_chunk(&mut manager, 2, 0);
+
+        assert_eq!(manager.chunks.len(), 10);
+        assert!(!manager.chunks.contains_key(&ChunkCoord::new(0, -1)));
+        assert!(!manager.chunks.contains_key(&ChunkCoord::new(0, 1)));
+        assert!(manager.chunks.contains_key(&ChunkCoord::new(0, 0)));
+    }
+
+    #[test]
+    fn modified_chunks_are_saved_and_reloaded() {
+        let world_dir = std::env::temp_dir().join(format!("roguelike_world_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&world_dir);
+        let road = chunk_center(0, 0);
+
+        let mut manager = ChunkManager::with_world_dir(12345, ChunkSettings::default(), &world_dir);
+        manager.set_tile(road.0, road.1, Tile::Road);
+        // Walking far enough away evicts the chunk, which writes it out
+        move_to_chunk(&mut manager, 20, 0);
+        assert!(!manager.chunks.contains_key(&ChunkCoord::new(0, 0)));
+        assert_eq!(manager.get_tile(road.0, road.1), Some(Tile::Road));
+
+        // Changes still in memory are written by an explicit save
+        let peak = chunk_center(20, 0);
+        manager.set_tile(peak.0, peak.1, Tile::Mountain);
+        manager.save_dirty_chunks().unwrap();
+
+        // A later session on the same world picks both up instead of regenerating
+        let mut reopened = ChunkManager::with_world_dir(12345, ChunkSettings::default(), &world_dir);
+        assert_eq!(reopened.get_tile(road.0, road.1), Some(Tile::Road));
+        assert_eq!(reopened.get_tile(peak.0, peak.1), Some(Tile::Mountain));
+        assert_eq!(reopened.chunks_generated(), 0);
+
+        fs::remove_dir_all(&world_dir).unwrap();
+    }
+
+    #[test]
+    fn biome_stats_cover_every_sampled_tile() {
+        let generator = InfiniteTerrainGenerator::new(12345);
+        let stats = generator.sample_biomes(0, 0, 100);
+
+        assert_eq!(stats.tiles_sampled, 201 * 201);
+        assert_eq!(stats.counts.iter().map(|(_, count)| count).sum::<usize>(), stats.tiles_sampled);
+        // A healthy world has room to walk and something to look at
+        assert!(stats.percent(Tile::Grass) + stats.percent(Tile::Tree) > 30.0, "{}", stats);
+        assert!(stats.percent(Tile::Grass) < 90.0, "{}", stats);
+        assert!(stats.count(Tile::Village) + stats.count(Tile::DungeonEntrance) > 0, "{}", stats);
+    }
+
+    #[test]
+    fn biome_thresholds_reshape_the_terrain() {
+        let count = |generator: &InfiniteTerrainGenerator, wanted: Tile| (0..60)
+            .flat_map(|y| (0..60).map(move |x| (x, y)))
+            .filter(|&(x, y)| generator.generate_tile_at(x, y) == wanted)
+            .count();
+        let standard = InfiniteTerrainGenerator::new(12345);
+        let mountainous = InfiniteTerrainGenerator::with_biomes(12345, BiomeConfig {
+            mountain_elevation: 0.5,
+            ..BiomeConfig::default()
+        });
+        let dry = InfiniteTerrainGenerator::with_biomes(12345, BiomeConfig {
+            lake_moisture: 1.0,
+            wetland_moisture: 1.0,
+            ..BiomeConfig::default()
+        });
+
+        assert!(count(&mountainous, Tile::Mountain) > count(&standard, Tile::Mountain));
+        assert!(count(&dry, Tile::Water) < count(&standard, Tile::Water));
+
+        assert!(BiomeConfig::default().validate().is_ok());
+        let invalid = BiomeConfig { lake_moisture: 1.5, ..BiomeConfig::default() };
+        assert!(invalid.validate().unwrap_err().contains("lake_moisture"));
+    }
+
+    fn tile_code(tile: Tile) -> char {
+        match tile {
+            Tile::Grass => 'G',
+            Tile::Tree => 'T',
+            Tile::Mountain => 'M',
+            Tile::Water => 'W',
+            Tile::Road => 'R',
+            Tile::Village => 'V',
+            Tile::DungeonEntrance => 'D',
+            Tile::Tower => 'I',
+            Tile::Ruins => 'U',
+            // Village streets, houses and walls
+            Tile::Floor => '.',
+            Tile::Wall => '#',
+            Tile::Door => '+',
+            other => panic!("overworld generated unexpected tile {:?}", other),
+        }
+    }
+
+    /// Golden sample of the overworld for seed 12345. If this fails the world
+    /// generation changed; only update the expected rows if that was intended.
+    #[test]
+    fn terrain_generation_matches_golden_sample() {
+        let generator = InfiniteTerrainGenerator::new(12345);
+        let expected = [
+            "GGGGGGGMGWGMGGGGGGGMGWGMG",
+            "GTTGMGGGTMGGTTWGTGGMTWTGT",
+            "MGTGGGGGWGWGGGGMGGGGMGGGG",
+            "GGGGWWTMMMTGGWTTGGMGGWWWG",
+            "RRGMMTWRGTTGGTGMGGGGRMGGT",
+            "GMGGGWGGGMGGGGGMGTGWGMGGG",
+            "GGGGGMGGMGGGWTMGGGWMMGWGG",
+            "GWWGMMMGGGGMGTGGMMGGGGTTG",
+            "MMGWT.GGGTMMMTTGGGMGGGWTM",
+            "GGGGGGGMMGGGGGTMMGGGTMGGG",
+            "GGGMG#GTGGGMGGGWGMGGGMGWG",
+        ];
+
+        // Rows y = -200..=200 step 40, columns x = -300..=300 step 25
+        let actual: Vec<String> = (-200..=200)
+            .step_by(40)
+            .map(|y| {
+                (-300..=300)
+                    .step_by(25)
+                    .map(|x| tile_code(generator.generate_tile_at(x, y)))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(actual, expected);
+
+        // The rare features are unlikely to land on the grid, so pin one of each
+        assert_eq!(generator.generate_tile_at(-72, -300), Tile::Road);
+        assert_eq!(generator.generate_tile_at(-209, -261), Tile::DungeonEntrance);
+        assert_eq!(generator.generate_tile_at(101, -243), Tile::Village);
+    }
+
+    #[test]
+    fn villages_are_walled_with_a_gate_on_each_side() {
+        let generator = InfiniteTerrainGenerator::new(12345);
+        let (center_x, center_y) = (101, -243); // Pinned in the golden sample
+        let r = VILLAGE_RADIUS;
+
+        assert_eq!(generator.generate_tile_at(center_x, center_y), Tile::Village);
+        for (dx, dy) in [(r, 0), (-r, 0), (0, r), (0, -r)] {
+            assert_eq!(generator.generate_tile_at(center_x + dx, center_y + dy), Tile::Door);
+        }
+        for (dx, dy) in [(r, r), (-r, 1), (2, -r), (-3, r)] {
+            assert_eq!(generator.generate_tile_at(center_x + dx, center_y + dy), Tile::Wall);
+        }
+        // Streets lead from the center to every gate
+        for step in 1..r {
+            for (dx, dy) in [(step, 0), (-step, 0), (0, step), (0, -step)] {
+                assert_eq!(generator.generate_tile_at(center_x + dx, center_y + dy), Tile::Floor);
+            }
+        }
+    }
+
+    #[test]
+    fn evicted_chunk_regenerates_identically() {
+        let mut manager = ChunkManager::new(12345, ChunkSettings::default());
+        let coord = ChunkCoord::new(0, 0);
+        manager.ensure_chunk_loaded(coord);
+        let original = manager.chunks[&coord].tiles.clone();
+
+        move_to_chunk(&mut manager, 20, 0);
+        assert!(!manager.chunks.contains_key(&coord));
+
+        manager.ensure_chunk_loaded(coord);
+        assert_eq!(manager.chunks[&coord].tiles, original);
+    }
+}
+
@@ -1,4 +1,6 @@
 // Shared constants to reduce duplication across client and server
+use serde::{Deserialize, Serialize};
+
 pub struct GameConstants;
 
 impl GameConstants {
@@ -7,27 +9,83 @@ impl GameConstants {
     pub const OVERWORLD_HEIGHT: i32 = 30;
     pub const DUNGEON_WIDTH: i32 = 40;
     pub const DUNGEON_HEIGHT: i32 = 20;
+    pub const MAX_NETWORK_MAP_TILES: i64 = 1 << 20; // Largest map accepted from the server, far above any dungeon
 
     // Spawn positions
     pub const OVERWORLD_SPAWN_X: i32 = 30;
     pub const OVERWORLD_SPAWN_Y: i32 = 15;
     pub const DUNGEON_SPAWN_X: i32 = 5;
     pub const DUNGEON_SPAWN_Y: i32 = 5;
+    pub const SPAWN_SEARCH_RADIUS: i32 = 64; // How far to look for a walkable overworld spawn
+
+    // Dungeon difficulty
+    pub const DUNGEON_DIFFICULTY_DISTANCE: i32 = 250; // Tiles from the origin per difficulty tier
+    pub const MAX_DUNGEON_DIFFICULTY: u32 = 5;
+    pub const EXTRA_ROOMS_PER_DIFFICULTY: i32 = 2; // Room placement attempts added per tier above 1
+    pub const WIDE_CORRIDOR_PERCENT: u32 = 30; // Share of dungeons whose corridors are 2 tiles wide
+    pub const SHORTCUT_PERCENT: u32 = 40; // Share of dungeons with shortcut exits in their deepest rooms
+    pub const MAX_DUNGEON_SHORTCUTS: usize = 2;
+    pub const SHORTCUT_MIN_ROOMS: usize = 4; // Smaller dungeons are quick enough to walk back through
 
     // Player stats
     pub const DEFAULT_HP: i32 = 20;
     pub const DEFAULT_MAX_HP: i32 = 20;
+    pub const EASY_HP: i32 = 30;
+    pub const HARD_HP: i32 = 12;
     pub const PLAYER_SYMBOL: char = '@';
+    pub const MAX_SPRINT_STEPS: u32 = 40; // Open grassland would otherwise never stop a sprint
+
+    // Survival mode
+    pub const MAX_HUNGER: i32 = 300; // Turns of walking on a full stomach
+    pub const HUNGRY_THRESHOLD: i32 = 60; // Warn the player below this
+    pub const FOOD_NUTRITION: i32 = 120;
+    pub const STARTING_FOOD: usize = 2;
 
     // UI constants
     pub const MAX_MESSAGES: usize = 10;
     pub const VIEWPORT_MIN_WIDTH: i32 = 60;
     pub const VIEWPORT_MIN_HEIGHT: i32 = 20;
+    pub const PLAYER_TRAIL_LENGTH: usize = 6; // Past positions drawn behind other players
+    pub const DUNGEON_REVEAL_RADIUS: i32 = 4; // Dungeon tiles around the player marked as explored
+    pub const DUNGEON_MINIMAP_SCALE: i32 = 2; // Dungeon tiles per minimap cell in each direction
+    pub const NAME_TAG_RADIUS: i32 = 15; // Other players within this many tiles get a name tag
+    pub const NAME_TAG_MAX_LEN: usize = 12; // Longer names are cut short on the map
+    pub const MAP_TRANSITION_MS: u64 = 400; // Fade-in after entering or leaving a dungeon
+    pub const COORDINATE_GRID_SPACING: i32 = 10; // World tiles between debug coordinate labels
+    pub const CHAT_HISTORY: usize = 50; // Default number of chat messages kept for scrolling back
+    pub const CHAT_SCROLL_STEP: usize = 5; // Messages moved per PageUp/PageDown
+    pub const MAX_CHAT_LENGTH: usize = 100; // Characters per chat message, enforced by the server too
+    // Smallest terminal the game screen fits in: the bordered viewport plus
+    // the status bar (3 rows), message log (5 rows) and room for the chat bar (3 rows)
+    pub const MIN_TERMINAL_WIDTH: u16 = Self::VIEWPORT_MIN_WIDTH as u16 + 2;
+    pub const MIN_TERMINAL_HEIGHT: u16 = Self::VIEWPORT_MIN_HEIGHT as u16 + 2 + 3 + 5 + 3;
+
+    // Persistence
+    pub const WORLD_SAVE_DIR: &'static str = "worlds"; // Modified overworld chunks are saved under here
+    pub const SINGLE_PLAYER_WORLD_FILE: &'static str = "single_player.json"; // In WORLD_SAVE_DIR, remembers the single player seed
+
+    // Configuration
+    pub const CLIENT_CONFIG_PATH: &'static str = "client_config.json"; // Optional, read at client startup
 
     // Network constants
     pub const DEFAULT_SERVER_ADDRESS: &'static str = "127.0.0.1:8080";
     pub const DEFAULT_PLAYER_NAME: &'static str = "Player";
-    pub const NETWORK_POLL_INTERVAL_MS: u64 = 50; // 20 FPS
+    pub const CONNECT_TIMEOUT_SECS: u64 = 5; // Default time to wait for a server to answer
+    pub const CONNECT_RETRY_BACKOFF_MS: u64 = 500; // Wait before the first retry, doubled after each
+    pub const DEFAULT_ROOM: &'static str = "default"; // World joined when no room code is given
+    pub const MAX_ROOM_CODE_LEN: usize = 16;
+    pub const MULTIPLAYER_WORLD_SEED: u32 = 12345; // Seed of the default room's world
+    pub const NETWORK_POLL_INTERVAL_MS: u64 = 50; // How often the client checks for server messages
+    pub const MAX_FPS: u32 = 120;
+    pub const AUTOSAVE_SECS: u64 = 60; // Default time between single player autosaves
+    pub const SAVED_INDICATOR_MS: u64 = 2000; // How long "Saved" shows in the status bar
+    pub const REDRAW_HEARTBEAT_MS: u64 = 1000; // Idle clients still redraw this often so time-based UI refreshes
+    pub const SERVER_STATS_INTERVAL_SECS: u64 = 60; // How often the server logs its stats
+    pub const SERVER_CHAT_HISTORY: usize = 20; // Chat messages each room replays to players joining it
+    pub const WORLD_STATS_RADIUS: i32 = 256; // Tiles around the origin sampled by --world-stats
+    pub const LAN_DISCOVERY_PORT: u16 = 8081; // UDP port LAN servers announce themselves on
+    pub const LAN_ANNOUNCE_INTERVAL_SECS: u64 = 2;
+    pub const LAN_SERVER_TIMEOUT_SECS: u64 = 6; // Servers not heard from for this long drop off the list
 
     // Game messages
     pub const MSG_WELCOME_SINGLE: &'static str = "Welcome to the overworld! Look for dungeons (D) to explore.";
@@ -43,3 +101,60 @@ impl GameConstants {
     pub const MSG_INVALID_POSITION: &'static str = "Invalid position.";
     pub const MSG_CONNECTED: &'static str = "Connected to server!";
 }
+
+/// Challenge presets: each sets the player's starting HP and how fast hunger
+/// drops in survival mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    /// The preset after this one, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    /// Starting (and maximum) HP
+    pub fn starting_hp(self) -> i32 {
+        match self {
+            Difficulty::Easy => GameConstants::EASY_HP,
+            Difficulty::Normal => GameConstants::DEFAULT_HP,
+            Difficulty::Hard => GameConstants::HARD_HP,
+        }
+    }
+
+    /// Hunger lost on the given turn: every other turn on easy, double on hard
+    pub fn hunger_loss(self, turn: u32) -> i32 {
+        match self {
+            Difficulty::Easy => (turn % 2) as i32,
+            Difficulty::Normal => 1,
+            Difficulty::Hard => 2,
+        }
+    }
+}
@@ -1,15 +1,25 @@
 // Shared game logic to reduce duplication between client and server
-use std::collections::HashMap;
-use super::protocol::{NetworkGameMap, coord_to_string, string_to_coord};
-use super::constants::GameConstants;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use super::protocol::{MapType, NetworkGameMap, NetworkPlayer, TileModification, decode_tiles, encode_tiles};
+use super::constants::{Difficulty, GameConstants};
 use super::terrain::TerrainGenerator;
-use super::chunk::ChunkManager;
+use super::item::Item;
 
 // Re-export common types that both client and server need
 pub use super::terrain::{Tile, GameMap};
-pub use super::chunk::{ChunkManager as GameChunkManager, ChunkCoord};
+pub use super::chunk::{ChunkManager as GameChunkManager, ChunkCoord, ChunkSettings};
 
-#[derive(Debug, Clone)]
+/// The world index entry that ties single player sessions to one world
+#[derive(Debug, Serialize, Deserialize)]
+struct SinglePlayerWorld {
+    seed: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Player {
     pub x: i32,
     pub y: i32,
@@ -17,6 +27,48 @@ pub struct Player {
     pub max_hp: i32,
     pub symbol: char,
     pub dungeon_entrance_pos: Option<(i32, i32)>, // Position of the dungeon entrance they came from
+    pub hunger: i32, // Only drains in survival mode
+    pub inventory: Vec<Item>,
+}
+
+impl Player {
+    /// Copies the server-authoritative state of a network player onto this player.
+    /// Every field shared with `NetworkPlayer` is synced here so new ones can't be missed.
+    pub fn apply_network_state(&mut self, network_player: &NetworkPlayer) {
+        self.x = network_player.x;
+        self.y = network_player.y;
+        self.hp = network_player.hp;
+        self.max_hp = network_player.max_hp;
+        self.symbol = network_player.symbol;
+        self.dungeon_entrance_pos = network_player.dungeon_entrance_pos;
+        self.inventory = network_player.inventory.clone();
+    }
+
+    /// Copies this player's state onto a network player, leaving network-only fields alone
+    pub fn apply_to_network(&self, network_player: &mut NetworkPlayer) {
+        network_player.x = self.x;
+        network_player.y = self.y;
+        network_player.hp = self.hp;
+        network_player.max_hp = self.max_hp;
+        network_player.symbol = self.symbol;
+        network_player.dungeon_entrance_pos = self.dungeon_entrance_pos;
+        network_player.inventory = self.inventory.clone();
+    }
+}
+
+impl From<&NetworkPlayer> for Player {
+    fn from(network_player: &NetworkPlayer) -> Self {
+        Player {
+            x: network_player.x,
+            y: network_player.y,
+            hp: network_player.hp,
+            max_hp: network_player.max_hp,
+            symbol: network_player.symbol,
+            dungeon_entrance_pos: network_player.dungeon_entrance_pos,
+            hunger: GameConstants::MAX_HUNGER,
+            inventory: network_player.inventory.clone(),
+        }
+    }
 }
 
 pub struct GameLogic;
@@ -26,7 +78,16 @@ impl GameLogic {
     pub fn is_movement_valid(tile: Tile) -> bool {
         matches!(tile, 
             Tile::Floor | Tile::Grass | Tile::Road | 
-            Tile::Tree | Tile::Village | Tile::DungeonEntrance | Tile::Door | Tile::DungeonExit
+            Tile::Tree | Tile::Village | Tile::DungeonEntrance | Tile::Door | Tile::DungeonExit |
+            Tile::DungeonShortcut | Tile::Tower | Tile::Ruins
+        )
+    }
+
+    /// Whether a sprint stops on this tile so the player can look around
+    pub fn stops_sprint(tile: Tile) -> bool {
+        matches!(tile,
+            Tile::Village | Tile::DungeonEntrance | Tile::Door | Tile::DungeonExit |
+            Tile::DungeonShortcut | Tile::Tower | Tile::Ruins
         )
     }
 
@@ -36,58 +97,219 @@ impl GameLogic {
             Tile::Wall => "You can't move through a wall.".to_string(),
             Tile::Mountain => "You can't move through a mountain.".to_string(),
             Tile::Water => "You can't swim across the water.".to_string(),
+            Tile::Empty => "There is nothing there to stand on.".to_string(),
             _ => "You can't move there.".to_string(),
         }
     }
 
+    /// Gets the message for trying to walk off the edge of a dungeon
+    pub fn get_dungeon_edge_message() -> String {
+        "The dungeon walls block your path.".to_string()
+    }
+
+    /// Checks whether a position lies inside a finite dungeon map
+    pub fn is_within_dungeon_bounds(dungeon_map: &GameMap, x: i32, y: i32) -> bool {
+        dungeon_map.in_bounds(x, y)
+    }
+
+    /// Validates a move inside a dungeon, returning the destination tile or the blocked message.
+    /// Anything outside the map (or missing from it) counts as the dungeon edge.
+    pub fn check_dungeon_move(dungeon_map: &GameMap, x: i32, y: i32) -> Result<Tile, String> {
+        match dungeon_map.tile_at(x, y) {
+            Some(tile) if Self::is_movement_valid(tile) => Ok(tile),
+            Some(tile) => Err(Self::get_blocked_movement_message(tile)),
+            None => Err(Self::get_dungeon_edge_message()),
+        }
+    }
+
+    /// Validates a move in the overworld, returning the destination tile or the blocked message.
+    /// The overworld is infinite, so there is no edge: only impassable terrain stops a move,
+    /// and a gap in the terrain (`None`) is walked over.
+    pub fn check_overworld_move(chunk_manager: &mut GameChunkManager, x: i32, y: i32) -> Result<Option<Tile>, String> {
+        match chunk_manager.get_tile(x, y) {
+            Some(tile) if !Self::is_movement_valid(tile) => Err(Self::get_blocked_movement_message(tile)),
+            tile => Ok(tile),
+        }
+    }
+
     /// Gets flavor text for moving to certain tiles
     pub fn get_tile_interaction_message(tile: Tile) -> Option<String> {
         match tile {
             Tile::Tree => Some("You push through the thick forest.".to_string()),
             Tile::Village => Some("You visit the village. The locals greet you warmly.".to_string()),
             Tile::DungeonEntrance => Some("You stand before a dark dungeon entrance. Press 'e' to enter.".to_string()),
+            Tile::Tower => Some("An old watchtower looms over you. You can see for miles from here.".to_string()),
+            Tile::Ruins => Some("You wander through crumbling ruins of a forgotten age.".to_string()),
             Tile::DungeonExit => Some("You are at the dungeon entrance/exit. Press 'x' to exit to the overworld.".to_string()),
+            Tile::DungeonShortcut => Some("A passage here leads back up to the entrance. Press 'x' to take it.".to_string()),
             _ => None,
         }
     }
 
+    /// Works out the tile a modification turns `tile` into, or why it can't be applied
+    pub fn apply_tile_modification(tile: Tile, modification: TileModification) -> Result<Tile, String> {
+        match (modification, tile) {
+            (TileModification::ChopTree, Tile::Tree) => Ok(Tile::Grass),
+            (TileModification::ChopTree, _) => Err("There is no tree here to chop.".to_string()),
+            (TileModification::LayRoad, Tile::Grass) => Ok(Tile::Road),
+            (TileModification::LayRoad, Tile::Road) => Err("There is already a road here.".to_string()),
+            (TileModification::LayRoad, _) => Err("You can only lay a road on grass.".to_string()),
+        }
+    }
+
+    /// Gets the message shown after successfully modifying a tile
+    pub fn get_tile_modification_message(modification: TileModification) -> String {
+        match modification {
+            TileModification::ChopTree => "You chop down the tree.".to_string(),
+            TileModification::LayRoad => "You lay down a stretch of road.".to_string(),
+        }
+    }
+
+    /// Gets the message shown after fast travelling to a village
+    pub fn get_fast_travel_message(x: i32, y: i32) -> String {
+        format!("You travel to the village at ({}, {}).", x, y)
+    }
+
+    /// Advances the player's hunger by one turn at the difficulty's rate, starving
+    /// them once it runs out. Returns a message when the player should be warned.
+    pub fn apply_hunger(player: &mut Player, difficulty: Difficulty, turn: u32) -> Option<String> {
+        if player.hunger > 0 {
+            let before = player.hunger;
+            player.hunger = (player.hunger - difficulty.hunger_loss(turn)).max(0);
+            if before > GameConstants::HUNGRY_THRESHOLD && player.hunger <= GameConstants::HUNGRY_THRESHOLD {
+                return Some("You are getting hungry.".to_string());
+            }
+            if player.hunger == 0 {
+                return Some("You are starving! Eat something soon.".to_string());
+            }
+            None
+        } else {
+            player.hp = (player.hp - 1).max(0);
+            Some("You are starving!".to_string())
+        }
+    }
+
+    /// Eats one food item from the player's inventory
+    pub fn eat_food(player: &mut Player) -> Result<String, String> {
+        let index = player.inventory.iter()
+            .position(|item| *item == Item::Food)
+            .ok_or_else(|| "You have nothing to eat.".to_string())?;
+
+        player.inventory.remove(index);
+        player.hunger = (player.hunger + GameConstants::FOOD_NUTRITION).min(GameConstants::MAX_HUNGER);
+        Ok("You eat some food. You feel less hungry.".to_string())
+    }
+
+    /// Whether chopping the tree at this position turns up something to eat
+    pub fn tree_has_food(x: i32, y: i32) -> bool {
+        let hash = (x.wrapping_mul(73_856_093) ^ y.wrapping_mul(19_349_663)) as u32;
+        hash.is_multiple_of(3)
+    }
+
+    /// Whether searching the ruins at this position turns up a recall scroll
+    pub fn ruins_have_scroll(x: i32, y: i32) -> bool {
+        let hash = (x.wrapping_mul(83_492_791) ^ y.wrapping_mul(50_331_653)) as u32;
+        hash.is_multiple_of(2)
+    }
+
+    /// Uses up a recall scroll from the inventory, returning the error message if
+    /// the player can't read one here. The caller moves the player to their entrance.
+    pub fn read_recall_scroll(inventory: &mut Vec<Item>, map_type: MapType) -> Result<(), String> {
+        let index = inventory.iter()
+            .position(|&item| item == Item::RecallScroll)
+            .ok_or_else(|| "You don't have a recall scroll.".to_string())?;
+        if map_type != MapType::Dungeon {
+            return Err("The scroll only works inside a dungeon.".to_string());
+        }
+        inventory.remove(index);
+        Ok(())
+    }
+
+    /// A rough look at the dungeon behind an entrance without going in, for players
+    /// carrying a spyglass. Dungeons are generated from their entrance (or the forced
+    /// seed), so this describes exactly the dungeon they would enter.
+    pub fn scout_dungeon(inventory: &[Item], entrance: Option<(i32, i32)>, seed_override: Option<u32>) -> Result<String, String> {
+        if !inventory.contains(&Item::Spyglass) {
+            return Err("You have nothing to scout with.".to_string());
+        }
+        let (x, y) = entrance.ok_or_else(|| "Stand on a dungeon entrance to look inside.".to_string())?;
+
+        let dungeon = Self::generate_dungeon_map(x, y, seed_override);
+        let difficulty = if seed_override.is_some() { 1 } else { Self::dungeon_difficulty(x, y) };
+        let open_tiles = dungeon.tiles.values().filter(|&tile| Self::is_movement_valid(tile)).count();
+        let shortcuts = dungeon.tiles.values().filter(|&tile| tile == Tile::DungeonShortcut).count();
+        Ok(format!(
+            "Through the spyglass you make out a {} dungeon (tier {}) with about {} open tiles{}.",
+            Self::dungeon_difficulty_name(difficulty),
+            difficulty,
+            (open_tiles + 5) / 10 * 10, // Only a rough look
+            if shortcuts > 0 { " and a way back out from deep inside" } else { "" },
+        ))
+    }
+
+    /// Normalizes a room code, falling back to the default room when none is given
+    pub fn normalize_room_code(code: Option<&str>) -> Result<String, String> {
+        let code = code.map(str::trim).unwrap_or_default().to_lowercase();
+        if code.is_empty() {
+            return Ok(GameConstants::DEFAULT_ROOM.to_string());
+        }
+
+        if code.len() > GameConstants::MAX_ROOM_CODE_LEN
+            || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "Room codes are up to {} letters, digits, '-' or '_'.",
+                GameConstants::MAX_ROOM_CODE_LEN
+            ));
+        }
+        Ok(code)
+    }
+
+    /// Strips control characters (newlines, escape codes) from a chat message and
+    /// cuts it to the length limit, so one client can't garble everyone's chat
+    pub fn sanitize_chat(message: &str) -> String {
+        message
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(GameConstants::MAX_CHAT_LENGTH)
+            .collect()
+    }
+
+    /// World seed for a room, so every room gets its own terrain
+    pub fn seed_for_room(room: &str) -> u32 {
+        if room == GameConstants::DEFAULT_ROOM {
+            return GameConstants::MULTIPLAYER_WORLD_SEED;
+        }
+
+        // FNV-1a, stable across builds unlike the std hasher
+        room.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193))
+    }
+
     /// Converts a GameMap to NetworkGameMap
     pub fn game_map_to_network(game_map: &GameMap) -> NetworkGameMap {
-        let network_tiles: HashMap<String, Tile> = game_map.tiles
-            .iter()
-            .map(|((x, y), tile)| (coord_to_string(*x, *y), *tile))
-            .collect();
-
         NetworkGameMap {
             width: game_map.width,
             height: game_map.height,
-            tiles: network_tiles,
+            tiles: encode_tiles(game_map.tiles.iter()),
         }
     }
 
-    /// Converts a NetworkGameMap to GameMap
-    pub fn network_map_to_game(network_map: &NetworkGameMap) -> GameMap {
-        let mut tiles = HashMap::new();
-        for (coord_str, tile) in &network_map.tiles {
-            if let Some((x, y)) = string_to_coord(coord_str) {
-                tiles.insert((x, y), *tile);
-            }
+    /// Converts a NetworkGameMap to GameMap, or explains which key was malformed
+    /// or why the map doesn't fit its size
+    pub fn network_map_to_game(network_map: &NetworkGameMap) -> Result<GameMap, String> {
+        let (width, height) = (network_map.width, network_map.height);
+        // The tile grid is allocated up front, so the size can't be left to the sender
+        if width < 0 || height < 0 || width as i64 * height as i64 > GameConstants::MAX_NETWORK_MAP_TILES {
+            return Err(format!("a {}x{} map is not a size we can hold", width, height));
         }
-        
-        GameMap {
-            width: network_map.width,
-            height: network_map.height,
-            tiles,
+        let mut game_map = GameMap::new(width, height);
+        for ((x, y), tile) in decode_tiles(&network_map.tiles)? {
+            if !game_map.in_bounds(x, y) {
+                return Err(format!("tile ({}, {}) is outside the {}x{} map", x, y, width, height));
+            }
+            game_map.tiles.insert((x, y), tile);
         }
-    }
-
-    /// Common logic for entering a dungeon - generates the dungeon map
-    pub fn generate_dungeon_map() -> GameMap {
-        // Use the sophisticated terrain generator from the terrain module
-        let width = GameConstants::DUNGEON_WIDTH;
-        let height = GameConstants::DUNGEON_HEIGHT;
-        
-        TerrainGenerator::generate_dungeon(width, height)
+        Ok(game_map)
     }
 
     /// Generate a dungeon map based on entrance position for uniqueness
@@ -97,8 +319,45 @@ impl GameLogic {
         
         // Generate a unique seed based on entrance position
         let seed = Self::generate_dungeon_seed(entrance_x, entrance_y);
+        let difficulty = Self::dungeon_difficulty(entrance_x, entrance_y);
         
-        TerrainGenerator::generate_dungeon_with_seed(width, height, seed)
+        TerrainGenerator::generate_dungeon_with_difficulty(width, height, seed, difficulty)
+    }
+
+    /// The dungeon behind an entrance, unless `seed_override` is set: then it's the
+    /// tier 1 dungeon for that seed wherever it was entered, so a layout can be shared
+    pub fn generate_dungeon_map(entrance_x: i32, entrance_y: i32, seed_override: Option<u32>) -> GameMap {
+        match seed_override {
+            Some(seed) => Self::generate_dungeon_map_with_seed(seed),
+            None => Self::generate_dungeon_map_for_entrance(entrance_x, entrance_y),
+        }
+    }
+
+    /// Difficulty tier of the dungeon behind an entrance, from 1 near the origin
+    /// up to `MAX_DUNGEON_DIFFICULTY` far out in the overworld
+    pub fn dungeon_difficulty(entrance_x: i32, entrance_y: i32) -> u32 {
+        // Widen before squaring so extreme coordinates can't overflow
+        let (x, y) = (entrance_x as f64, entrance_y as f64);
+        let distance = (x * x + y * y).sqrt();
+        let tier = (distance / GameConstants::DUNGEON_DIFFICULTY_DISTANCE as f64) as u32 + 1;
+        tier.min(GameConstants::MAX_DUNGEON_DIFFICULTY)
+    }
+
+    /// Adjective describing a difficulty tier in entry messages
+    pub fn dungeon_difficulty_name(difficulty: u32) -> &'static str {
+        match difficulty {
+            0 | 1 => "quiet",
+            2 => "gloomy",
+            3 => "dangerous",
+            4 => "perilous",
+            _ => "deadly",
+        }
+    }
+
+    /// Message shown when entering the dungeon behind an entrance
+    pub fn get_dungeon_entry_message(entrance_x: i32, entrance_y: i32) -> String {
+        let difficulty = Self::dungeon_difficulty(entrance_x, entrance_y);
+        format!("You enter a {} dungeon... (tier {})", Self::dungeon_difficulty_name(difficulty), difficulty)
     }
 
     /// Generate a unique seed for a dungeon based on its entrance position
@@ -112,7 +371,13 @@ impl GameLogic {
         seed = seed ^ (seed >> 13);
         seed = seed.wrapping_mul(0xc2b2ae35);
         seed = seed ^ (seed >> 16);
-        seed
+
+        // Zero is a degenerate seed for the generator's LCG, swap it for the base seed
+        if seed == 0 {
+            0x9e3779b9
+        } else {
+            seed
+        }
     }
 
     /// Common logic for exiting to overworld - generates the overworld map
@@ -131,9 +396,7 @@ impl GameLogic {
         let width = GameConstants::DUNGEON_WIDTH;
         let height = GameConstants::DUNGEON_HEIGHT;
         
-        // For now, we use the standard generator but could enhance it with seed support
-        let _ = seed; // Acknowledge the parameter for future use
-        TerrainGenerator::generate_dungeon(width, height)
+        TerrainGenerator::generate_dungeon_with_seed(width, height, seed)
     }
 
     /// Get default dungeon spawn position - now finds a safe floor tile
@@ -154,32 +417,63 @@ impl GameLogic {
             }
         }
         
-        // If no dungeon exit found, try the default spawn position
+        // If no dungeon exit found, use the walkable tile nearest the default spawn position
         let default_pos = (GameConstants::DUNGEON_SPAWN_X, GameConstants::DUNGEON_SPAWN_Y);
-        if let Some(tile) = dungeon_map.tiles.get(&default_pos) {
-            if *tile == Tile::Floor {
-                return default_pos;
-            }
-        }
+        Self::nearest_walkable(|x, y| dungeon_map.tile_at(x, y), default_pos, &HashSet::new())
+    }
 
-        // If default position is not safe, find the first floor tile
-        for y in 1..dungeon_map.height - 1 {
-            for x in 1..dungeon_map.width - 1 {
-                if let Some(tile) = dungeon_map.tiles.get(&(x, y)) {
-                    if *tile == Tile::Floor {
+    /// Get default overworld spawn position
+    pub fn get_overworld_spawn_position() -> (i32, i32) {
+        (GameConstants::OVERWORLD_SPAWN_X, GameConstants::OVERWORLD_SPAWN_Y)
+    }
+
+    /// `nearest_walkable` over the overworld chunks with nothing occupied
+    pub fn find_safe_overworld_spawn(chunk_manager: &mut GameChunkManager, base: (i32, i32)) -> (i32, i32) {
+        Self::find_free_overworld_spawn(chunk_manager, base, &HashSet::new())
+    }
+
+    /// Like `find_safe_overworld_spawn`, but also skips tiles in `occupied` so
+    /// players joining a shared world don't stack on each other
+    pub fn find_free_overworld_spawn(
+        chunk_manager: &mut GameChunkManager,
+        base: (i32, i32),
+        occupied: &HashSet<(i32, i32)>,
+    ) -> (i32, i32) {
+        Self::nearest_walkable(|x, y| chunk_manager.get_tile(x, y), base, occupied)
+    }
+
+    /// Where to put a player who should be at `target`: the nearest walkable tile
+    /// not in `occupied`, searching outward ring by ring. The tile also needs a
+    /// walkable neighbour so the player can't be placed boxed in. Every spawn and
+    /// teleport goes through here.
+    pub fn nearest_walkable(
+        mut tile_at: impl FnMut(i32, i32) -> Option<Tile>,
+        target: (i32, i32),
+        occupied: &HashSet<(i32, i32)>,
+    ) -> (i32, i32) {
+        let (base_x, base_y) = target;
+        let mut is_walkable = |x: i32, y: i32| tile_at(x, y).is_some_and(Self::is_movement_valid);
+
+        for radius in 0..=GameConstants::SPAWN_SEARCH_RADIUS {
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if dx.abs().max(dy.abs()) != radius {
+                        continue; // Inner rings were already checked
+                    }
+
+                    let (x, y) = (base_x + dx, base_y + dy);
+                    if !occupied.contains(&(x, y))
+                        && is_walkable(x, y)
+                        && [(0, -1), (0, 1), (-1, 0), (1, 0)].iter().any(|(nx, ny)| is_walkable(x + nx, y + ny))
+                    {
                         return (x, y);
                     }
                 }
             }
         }
 
-        // Fallback to default position (should not happen with proper generation)
-        default_pos
-    }
-
-    /// Get default overworld spawn position
-    pub fn get_overworld_spawn_position() -> (i32, i32) {
-        (GameConstants::OVERWORLD_SPAWN_X, GameConstants::OVERWORLD_SPAWN_Y)
+        // Nothing walkable nearby (should not happen with the generators)
+        target
     }
 
     /// Check if current position has a dungeon entrance
@@ -192,21 +486,56 @@ impl GameLogic {
         game_map.get_tile(x, y) == Some(&Tile::DungeonEntrance)
     }
 
-    /// Check if current position has a dungeon exit
+    /// Check if current position has a dungeon exit or shortcut
     pub fn is_at_dungeon_exit(game_map: &GameMap, x: i32, y: i32) -> bool {
-        game_map.tiles.get(&(x, y)) == Some(&Tile::DungeonExit)
+        matches!(game_map.tiles.get(&(x, y)), Some(Tile::DungeonExit | Tile::DungeonShortcut))
     }
 
     /// Limit messages to a maximum count
-    pub fn limit_messages(messages: &mut Vec<String>, max_count: usize) {
+    pub fn limit_messages<T>(messages: &mut Vec<T>, max_count: usize) {
         while messages.len() > max_count {
             messages.remove(0);
         }
     }
 
     /// Create a new chunk manager with infinite terrain
-    pub fn create_chunk_manager(seed: u32) -> GameChunkManager {
-        GameChunkManager::new(seed)
+    pub fn create_chunk_manager(seed: u32, settings: ChunkSettings) -> GameChunkManager {
+        GameChunkManager::new(seed, settings)
+    }
+
+    /// Directory where the modified chunks of the world with this seed are stored
+    pub fn world_dir_for_seed(seed: u32) -> PathBuf {
+        PathBuf::from(GameConstants::WORLD_SAVE_DIR).join(format!("seed_{}", seed))
+    }
+
+    /// Directory where a multiplayer room's modified chunks are stored. Keyed by
+    /// the room code rather than its seed, since two codes can hash to one seed.
+    pub fn world_dir_for_room(room: &str) -> PathBuf {
+        PathBuf::from(GameConstants::WORLD_SAVE_DIR).join(format!("room_{}", room))
+    }
+
+    /// Seed of the single player world. It is picked once and kept in the world
+    /// index, so every session continues the world (and saved chunks) of the last.
+    pub fn single_player_world_seed() -> io::Result<u32> {
+        let path = PathBuf::from(GameConstants::WORLD_SAVE_DIR).join(GameConstants::SINGLE_PLAYER_WORLD_FILE);
+        match fs::read_to_string(&path) {
+            Ok(json) => Ok(serde_json::from_str::<SinglePlayerWorld>(&json)?.seed),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let seed = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as u32;
+                fs::create_dir_all(GameConstants::WORLD_SAVE_DIR)?;
+                fs::write(&path, serde_json::to_string(&SinglePlayerWorld { seed })?)?;
+                Ok(seed)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Create a chunk manager whose modified chunks persist across eviction
+    pub fn create_persistent_chunk_manager(seed: u32, settings: ChunkSettings) -> GameChunkManager {
+        GameChunkManager::with_world_dir(seed, settings, Self::world_dir_for_seed(seed))
     }
 
     /// Check if current position has a dungeon entrance (chunk manager version)
@@ -254,7 +583,7 @@ impl PlayerOperations for Player {
 }
 
 // Implement for NetworkPlayer
-impl PlayerOperations for super::protocol::NetworkPlayer {
+impl PlayerOperations for NetworkPlayer {
     fn get_position(&self) -> (i32, i32) {
         (self.x, self.y)
     }
@@ -272,3 +601,302 @@ impl PlayerOperations for super::protocol::NetworkPlayer {
         self.hp = hp;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::protocol::NetworkCurrentScreen;
+
+    fn open_dungeon(width: i32, height: i32) -> GameMap {
+        let mut dungeon = GameMap::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                dungeon.tiles.insert((x, y), Tile::Floor);
+            }
+        }
+        dungeon
+    }
+
+    #[test]
+    fn empty_tiles_are_impassable_void() {
+        assert!(!GameLogic::is_movement_valid(Tile::Empty));
+        assert_eq!(GameLogic::get_blocked_movement_message(Tile::Empty), "There is nothing there to stand on.");
+        assert_eq!(GameLogic::get_tile_interaction_message(Tile::Empty), None);
+
+        let mut dungeon = open_dungeon(5, 5);
+        dungeon.tiles.insert((2, 2), Tile::Empty);
+        assert_eq!(
+            GameLogic::check_dungeon_move(&dungeon, 2, 2),
+            Err("There is nothing there to stand on.".to_string())
+        );
+    }
+
+    #[test]
+    fn freshly_generated_water_blocks_overworld_moves() {
+        let seed = 1234;
+        let mut scout = GameLogic::create_chunk_manager(seed, ChunkSettings::default());
+        let find = |scout: &mut GameChunkManager, wanted: Tile| (0..400)
+            .flat_map(|y| (0..400).map(move |x| (10_000 + x, -10_000 + y)))
+            .find(|&(x, y)| scout.get_tile(x, y) == Some(wanted))
+            .expect("tile not found in the search area");
+        let water = find(&mut scout, Tile::Water);
+        let grass = find(&mut scout, Tile::Grass);
+
+        // A fresh manager generates the chunk while checking the move
+        let mut chunk_manager = GameLogic::create_chunk_manager(seed, ChunkSettings::default());
+        assert_eq!(
+            GameLogic::check_overworld_move(&mut chunk_manager, water.0, water.1),
+            Err("You can't swim across the water.".to_string())
+        );
+        assert_eq!(GameLogic::check_overworld_move(&mut chunk_manager, grass.0, grass.1), Ok(Some(Tile::Grass)));
+    }
+
+    #[test]
+    fn walking_into_dungeon_corner_stops_at_edge() {
+        let dungeon = open_dungeon(5, 5);
+        let (mut x, mut y) = (2, 2);
+
+        let error = loop {
+            match GameLogic::check_dungeon_move(&dungeon, x - 1, y - 1) {
+                Ok(_) => {
+                    x -= 1;
+                    y -= 1;
+                }
+                Err(message) => break message,
+            }
+        };
+
+        assert_eq!((x, y), (0, 0));
+        assert_eq!(error, GameLogic::get_dungeon_edge_message());
+        assert_eq!(GameLogic::check_dungeon_move(&dungeon, 0, -1), Err(GameLogic::get_dungeon_edge_message()));
+        assert_eq!(GameLogic::check_dungeon_move(&dungeon, 5, 4), Err(GameLogic::get_dungeon_edge_message()));
+    }
+
+    fn network_player() -> NetworkPlayer {
+        NetworkPlayer {
+            id: "player-1".to_string(),
+            name: "Tester".to_string(),
+            x: 12,
+            y: -7,
+            hp: 13,
+            max_hp: 25,
+            symbol: '&',
+            current_screen: NetworkCurrentScreen::Game,
+            color: (1, 2, 3),
+            current_map_type: MapType::Dungeon,
+            dungeon_entrance_pos: Some((40, 41)),
+            inventory: vec![Item::Food, Item::RecallScroll],
+        }
+    }
+
+    #[test]
+    fn network_maps_must_fit_their_size() {
+        let dungeon = GameLogic::generate_dungeon_map_with_seed(3);
+        let network_map = GameLogic::game_map_to_network(&dungeon);
+        assert_eq!(GameLogic::network_map_to_game(&network_map).unwrap().tiles, dungeon.tiles);
+
+        let mut outside = network_map.clone();
+        outside.tiles.insert("0_-1".to_string(), Tile::Floor);
+        assert!(GameLogic::network_map_to_game(&outside).unwrap_err().contains("outside"));
+        let huge = NetworkGameMap { width: i32::MAX, height: i32::MAX, tiles: HashMap::new() };
+        assert!(GameLogic::network_map_to_game(&huge).is_err());
+    }
+
+    #[test]
+    fn player_round_trips_through_network_player() {
+        let original = network_player();
+        let player = Player::from(&original);
+
+        let mut synced = Player {
+            x: 0,
+            y: 0,
+            hp: 0,
+            max_hp: 0,
+            symbol: '@',
+            dungeon_entrance_pos: None,
+            hunger: GameConstants::MAX_HUNGER,
+            inventory: Vec::new(),
+        };
+        synced.apply_network_state(&original);
+        assert_eq!(synced, player);
+
+        let mut round_trip = NetworkPlayer {
+            x: 0,
+            y: 0,
+            hp: 0,
+            max_hp: 0,
+            symbol: '@',
+            dungeon_entrance_pos: None,
+            inventory: Vec::new(),
+            ..original.clone()
+        };
+        player.apply_to_network(&mut round_trip);
+        assert_eq!(round_trip, original);
+    }
+
+    #[test]
+    fn hunger_drops_at_the_difficulty_rate_and_still_warns() {
+        let starving_turns = |difficulty: Difficulty| {
+            let mut player = Player {
+                x: 0,
+                y: 0,
+                hp: difficulty.starting_hp(),
+                max_hp: difficulty.starting_hp(),
+                symbol: '@',
+                dungeon_entrance_pos: None,
+                hunger: GameConstants::MAX_HUNGER,
+                inventory: Vec::new(),
+            };
+            let mut warnings = Vec::new();
+            let mut turn = 0;
+            while player.hunger > 0 {
+                turn += 1;
+                warnings.extend(GameLogic::apply_hunger(&mut player, difficulty, turn));
+            }
+            assert_eq!(warnings, ["You are getting hungry.", "You are starving! Eat something soon."]);
+            turn
+        };
+
+        let normal = starving_turns(Difficulty::Normal);
+        assert_eq!(normal, GameConstants::MAX_HUNGER as u32);
+        // Easy loses a point on odd turns, so it runs out on the turn before twice as many
+        assert_eq!(starving_turns(Difficulty::Easy), normal * 2 - 1);
+        assert_eq!(starving_turns(Difficulty::Hard), normal / 2);
+        assert!(Difficulty::Easy.starting_hp() > Difficulty::Normal.starting_hp());
+        assert!(Difficulty::Hard.starting_hp() < Difficulty::Normal.starting_hp());
+    }
+
+    #[test]
+    fn scouting_a_dungeon_needs_a_spyglass_and_an_entrance() {
+        let entrance = Some((-209, -261));
+        assert!(GameLogic::scout_dungeon(&[Item::Food], entrance, None).is_err());
+        assert!(GameLogic::scout_dungeon(&[Item::Spyglass], None, None).is_err());
+
+        let preview = GameLogic::scout_dungeon(&[Item::Spyglass], entrance, None).unwrap();
+        assert!(preview.contains(&format!("(tier {})", GameLogic::dungeon_difficulty(-209, -261))), "{}", preview);
+        // Looking is free: the spyglass isn't used up
+        let inventory = vec![Item::Spyglass];
+        GameLogic::scout_dungeon(&inventory, entrance, None).unwrap();
+        assert_eq!(inventory, [Item::Spyglass]);
+        assert!(GameLogic::scout_dungeon(&inventory, entrance, Some(7)).unwrap().contains("(tier 1)"));
+    }
+
+    #[test]
+    fn nearest_walkable_avoids_water_mountains_pockets_and_players() {
+        // A 7x7 grass field around (3,3) with water, a mountain and a walled-in pocket
+        let mut map = GameMap::new(7, 7);
+        for y in 0..7 {
+            for x in 0..7 {
+                map.tiles.insert((x, y), Tile::Grass);
+            }
+        }
+        map.tiles.insert((3, 3), Tile::Water);
+        map.tiles.insert((2, 3), Tile::Water);
+        map.tiles.insert((4, 3), Tile::Mountain);
+        let none = HashSet::new();
+
+        let from_water = GameLogic::nearest_walkable(|x, y| map.tile_at(x, y), (3, 3), &none);
+        assert_eq!(from_water, (2, 2), "first walkable tile on the nearest ring");
+        let from_mountain = GameLogic::nearest_walkable(|x, y| map.tile_at(x, y), (4, 3), &none);
+        assert_eq!(from_mountain, (3, 2));
+
+        // Floor with walls on all four sides can't be stood on
+        map.tiles.insert((5, 5), Tile::Floor);
+        for (x, y) in [(5, 4), (5, 6), (4, 5), (6, 5)] {
+            map.tiles.insert((x, y), Tile::Wall);
+        }
+        let pocket = GameLogic::nearest_walkable(|x, y| map.tile_at(x, y), (5, 5), &none);
+        assert_ne!(pocket, (5, 5));
+        assert!(GameLogic::is_movement_valid(map.tile_at(pocket.0, pocket.1).unwrap()));
+
+        let occupied = HashSet::from([(2, 2), (3, 2)]);
+        assert_eq!(GameLogic::nearest_walkable(|x, y| map.tile_at(x, y), (3, 3), &occupied), (4, 2));
+        assert_eq!(GameLogic::nearest_walkable(|x, y| map.tile_at(x, y), (1, 1), &none), (1, 1));
+    }
+
+    #[test]
+    fn overworld_spawn_is_walkable_for_many_seeds() {
+        for seed in [0, 1, 7, 42, 12345, 99999, 0xdead_beef, u32::MAX] {
+            let mut chunk_manager = GameChunkManager::new(seed, ChunkSettings::default());
+            let (x, y) = GameLogic::find_safe_overworld_spawn(
+                &mut chunk_manager,
+                GameLogic::get_overworld_spawn_position(),
+            );
+            let tile = chunk_manager.get_tile(x, y).unwrap();
+            assert!(GameLogic::is_movement_valid(tile), "seed {} spawned on {:?}", seed, tile);
+        }
+    }
+
+    #[test]
+    fn dungeon_seeds_are_distinct_for_nearby_entrances() {
+        let mut seen = std::collections::HashSet::new();
+        for x in -20..=20 {
+            for y in -20..=20 {
+                let seed = GameLogic::generate_dungeon_seed(x, y);
+                assert_ne!(seed, 0, "entrance ({}, {}) got a zero seed", x, y);
+                assert!(seen.insert(seed), "entrance ({}, {}) shares a seed with another entrance", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn dungeon_seed_handles_extreme_coordinates() {
+        let extremes = [i32::MIN, i32::MIN + 1, -1, 0, 1, i32::MAX - 1, i32::MAX];
+        for &x in &extremes {
+            for &y in &extremes {
+                let seed = GameLogic::generate_dungeon_seed(x, y);
+                assert_ne!(seed, 0);
+                assert_eq!(seed, GameLogic::generate_dungeon_seed(x, y));
+            }
+        }
+        assert_ne!(
+            GameLogic::generate_dungeon_seed(i32::MIN, i32::MAX),
+            GameLogic::generate_dungeon_seed(i32::MAX, i32::MIN)
+        );
+    }
+
+    #[test]
+    fn dungeon_walls_keep_their_own_message() {
+        let mut dungeon = open_dungeon(3, 3);
+        dungeon.tiles.insert((0, 0), Tile::Wall);
+
+        assert_eq!(
+            GameLogic::check_dungeon_move(&dungeon, 0, 0),
+            Err(GameLogic::get_blocked_movement_message(Tile::Wall))
+        );
+        assert_eq!(GameLogic::check_dungeon_move(&dungeon, 1, 1), Ok(Tile::Floor));
+    }
+
+    #[test]
+    fn dungeons_are_reproducible_from_their_entrance() {
+        for (x, y) in [(0, 0), (-209, -261), (1000, -1000)] {
+            let first = GameLogic::generate_dungeon_map_for_entrance(x, y);
+            let second = GameLogic::generate_dungeon_map_for_entrance(x, y);
+            assert_eq!(first.tiles, second.tiles);
+        }
+
+        let seed = GameLogic::generate_dungeon_seed(5, 5);
+        assert_eq!(
+            GameLogic::generate_dungeon_map_with_seed(seed).tiles,
+            GameLogic::generate_dungeon_map_with_seed(seed).tiles
+        );
+
+        // A forced seed gives the same layout behind every entrance
+        let forced = GameLogic::generate_dungeon_map(0, 0, Some(seed));
+        assert_eq!(GameLogic::generate_dungeon_map(1000, -1000, Some(seed)).tiles, forced.tiles);
+        assert_eq!(
+            GameLogic::generate_dungeon_map(1000, -1000, None).tiles,
+            GameLogic::generate_dungeon_map_for_entrance(1000, -1000).tiles
+        );
+    }
+
+    #[test]
+    fn dungeon_difficulty_grows_with_distance_and_caps() {
+        let step = GameConstants::DUNGEON_DIFFICULTY_DISTANCE;
+        assert_eq!(GameLogic::dungeon_difficulty(0, 0), 1);
+        assert_eq!(GameLogic::dungeon_difficulty(step - 1, 0), 1);
+        assert_eq!(GameLogic::dungeon_difficulty(0, -step), 2);
+        assert_eq!(GameLogic::dungeon_difficulty(-3 * step, 0), 4);
+        assert_eq!(GameLogic::dungeon_difficulty(i32::MIN, i32::MAX), GameConstants::MAX_DUNGEON_DIFFICULTY);
+    }
+}
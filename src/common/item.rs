@@ -0,0 +1,46 @@
+// Items that can be carried in a player's inventory
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Item {
+    Food, // Restores hunger in survival mode
+    RecallScroll, // Returns the reader from a dungeon to its entrance
+    Spyglass, // Shows what lies behind a dungeon entrance before going in; never used up
+}
+
+impl Item {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Item::Food => "Food",
+            Item::RecallScroll => "Recall Scroll",
+            Item::Spyglass => "Spyglass",
+        }
+    }
+
+    /// Group identical items for display, in the order each was first picked up
+    pub fn stack(items: &[Item]) -> Vec<(Item, usize)> {
+        let mut stacks: Vec<(Item, usize)> = Vec::new();
+        for &item in items {
+            match stacks.iter_mut().find(|(stacked, _)| *stacked == item) {
+                Some((_, count)) => *count += 1,
+                None => stacks.push((item, 1)),
+            }
+        }
+        stacks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacks_group_identical_items_in_pickup_order() {
+        let items = [Item::RecallScroll, Item::Food, Item::RecallScroll, Item::Spyglass, Item::Food, Item::RecallScroll];
+        assert_eq!(
+            Item::stack(&items),
+            vec![(Item::RecallScroll, 3), (Item::Food, 2), (Item::Spyglass, 1)]
+        );
+        assert!(Item::stack(&[]).is_empty());
+    }
+}
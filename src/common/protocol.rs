@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use super::game_logic::Tile;
+use super::item::Item;
 
 pub type PlayerId = String;
 
@@ -17,20 +18,37 @@ pub enum CurrentScreen {
     Game,
     Inventory,
     Chat,
+    FastTravel,
+    Help,
     Exiting,
 }
 
+// Ways a player can reshape the overworld tile they're working on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileModification {
+    ChopTree, // Tree -> Grass
+    LayRoad,  // Grass -> Road
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ClientMessage {
-    Connect { player_name: String },
+    Connect {
+        player_name: String,
+        #[serde(default)]
+        room: Option<String>, // World to join; unknown codes create a new one
+    },
     Move { dx: i32, dy: i32 },
     RequestChunks { chunks: Vec<(i32, i32)> }, // Request specific chunk coordinates
     RequestDungeonData, // Request current dungeon map
     EnterDungeon,
     ExitDungeon,
+    ModifyTile { x: i32, y: i32, modification: TileModification },
+    FastTravel { x: i32, y: i32 }, // Travel to a previously visited village
     OpenInventory,
     CloseInventory,
     Chat { message: String },
+    UseItem { item: Item }, // Use one of the player's items; the server checks they have it
+    Wait, // Let a turn pass without moving
     Disconnect,
 }
 
@@ -41,11 +59,81 @@ pub enum ServerMessage {
     ChunkData { chunks: Vec<ChunkData> }, // Send chunk data to clients
     DungeonData { dungeon_map: NetworkGameMap }, // Send dungeon map to clients
     PlayerMoved { player_id: PlayerId, x: i32, y: i32 },
+    TileChanged { x: i32, y: i32, tile: Tile }, // An overworld tile was modified by a player
     PlayerJoined { player_id: PlayerId, player: NetworkPlayer },
     PlayerLeft { player_id: PlayerId },
     Error { message: String },
     Message { text: String },
     ChatMessage { player_name: String, message: String },
+    ChatHistory { messages: Vec<(String, String)> }, // Recent (player_name, message) pairs, sent on joining
+    // Never sent; stands in for variants added by newer servers (see `ServerMessage::parse`)
+    #[serde(skip)]
+    Unknown { kind: String },
+}
+
+impl ServerMessage {
+    /// Parse a message from the server. A variant this build doesn't know about
+    /// becomes `Unknown` instead of an error so the client can warn about it.
+    /// (Serde's `#[serde(other)]` only works for internally tagged enums.)
+    pub fn parse(text: &str) -> Result<Self, serde_json::Error> {
+        let value = serde_json::from_str::<serde_json::Value>(text)?;
+        // Externally tagged: unit variants are a bare string, the rest `{"Variant": ...}`
+        let kind = match &value {
+            serde_json::Value::String(kind) => Some(kind),
+            serde_json::Value::Object(fields) if fields.len() == 1 => fields.keys().next(),
+            _ => None,
+        };
+        match kind {
+            Some(kind) if !enum_variants::<ServerMessage>().contains(&kind.as_str()) => {
+                Ok(ServerMessage::Unknown { kind: kind.clone() })
+            }
+            _ => serde_json::from_value(value),
+        }
+    }
+}
+
+/// The variant names an enum's derived `Deserialize` accepts. The derive hands
+/// them to `deserialize_enum`, so a deserializer that only records them and
+/// then gives up is enough to read them.
+fn enum_variants<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    use serde::de::{self, Visitor};
+
+    struct VariantNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> de::Deserializer<'de> for VariantNames<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not an enum"))
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            variants: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = variants;
+            Err(de::Error::custom("only the variant names were wanted"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map
+            struct identifier ignored_any
+        }
+    }
+
+    let mut variants: &'static [&'static str] = &[];
+    let _ = T::deserialize(VariantNames(&mut variants));
+    variants
+}
+
+/// Broadcast over UDP by servers started with --lan so clients can list them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanAnnouncement {
+    pub port: u16, // WebSocket port; the address is wherever the announcement came from
+    pub players: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,7 +144,7 @@ pub struct GameState {
     // Note: current_map_type is now per-player
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NetworkPlayer {
     pub id: PlayerId,
     pub name: String,
@@ -69,6 +157,8 @@ pub struct NetworkPlayer {
     pub color: (u8, u8, u8), // RGB color tuple for this player
     pub current_map_type: MapType, // Each player can be in a different map
     pub dungeon_entrance_pos: Option<(i32, i32)>, // Position of the dungeon entrance they came from
+    #[serde(default)]
+    pub inventory: Vec<Item>, // Kept by the server, which checks it when items are used; empty for other players
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,7 +172,7 @@ pub struct NetworkGameMap {
 pub struct ChunkData {
     pub chunk_x: i32,
     pub chunk_y: i32,
-    pub tiles: HashMap<String, Tile>, // Local coordinates as string keys (e.g., "0,0" to "31,31")
+    pub tiles: HashMap<String, Tile>, // Local coordinates keyed by `coord_to_string` ("0_0" to "31_31")
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -100,6 +190,8 @@ impl From<CurrentScreen> for NetworkCurrentScreen {
             CurrentScreen::Game => NetworkCurrentScreen::Game,
             CurrentScreen::Inventory => NetworkCurrentScreen::Inventory,
             CurrentScreen::Chat => NetworkCurrentScreen::Chat,
+            CurrentScreen::FastTravel => NetworkCurrentScreen::Game, // Still on the map as far as others can tell
+            CurrentScreen::Help => NetworkCurrentScreen::Game,
             CurrentScreen::Exiting => NetworkCurrentScreen::Exiting,
         }
     }
@@ -116,19 +208,26 @@ impl From<NetworkCurrentScreen> for CurrentScreen {
     }
 }
 
-// Helper functions for coordinate conversion
+// JSON object keys have to be strings, so tile maps are sent keyed by "x_y".
+// Every map on the wire goes through these helpers.
 pub fn coord_to_string(x: i32, y: i32) -> String {
     format!("{}_{}", x, y)
 }
 
-pub fn string_to_coord(s: &str) -> Option<(i32, i32)> {
-    let parts: Vec<&str> = s.split('_').collect();
-    if parts.len() == 2 {
-        if let (Ok(x), Ok(y)) = (parts[0].parse::<i32>(), parts[1].parse::<i32>()) {
-            return Some((x, y));
-        }
-    }
-    None
+pub fn string_to_coord(s: &str) -> Result<(i32, i32), String> {
+    s.split_once('_')
+        .and_then(|(x, y)| Some((x.parse::<i32>().ok()?, y.parse::<i32>().ok()?)))
+        .ok_or_else(|| format!("invalid coordinate key '{}'", s))
+}
+
+/// Key tiles by their coordinate strings for sending
+pub fn encode_tiles(tiles: impl IntoIterator<Item = ((i32, i32), Tile)>) -> HashMap<String, Tile> {
+    tiles.into_iter().map(|((x, y), tile)| (coord_to_string(x, y), tile)).collect()
+}
+
+/// Read back tiles sent with `encode_tiles`, rejecting the whole map on a malformed key
+pub fn decode_tiles(tiles: &HashMap<String, Tile>) -> Result<HashMap<(i32, i32), Tile>, String> {
+    tiles.iter().map(|(key, tile)| Ok((string_to_coord(key)?, *tile))).collect()
 }
 
 impl NetworkGameMap {
@@ -136,3 +235,42 @@ impl NetworkGameMap {
         self.tiles.get(&coord_to_string(x, y))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinate_keys_round_trip_including_negatives() {
+        for (x, y) in [(0, 0), (31, 31), (-1, 5), (7, -12), (-300, -40), (i32::MIN, i32::MAX)] {
+            assert_eq!(string_to_coord(&coord_to_string(x, y)), Ok((x, y)));
+        }
+        assert_eq!(coord_to_string(-3, 12), "-3_12");
+
+        for bad in ["", "3", "3,4", "3_", "_4", "a_4", "3_4_5"] {
+            assert!(string_to_coord(bad).is_err(), "'{}' should not parse", bad);
+        }
+
+        let tiles = HashMap::from([((-2, 3), Tile::Grass), ((4, -5), Tile::Water)]);
+        assert_eq!(decode_tiles(&encode_tiles(tiles.clone())), Ok(tiles));
+        let mut sent = encode_tiles([((1, 1), Tile::Road)]);
+        sent.insert("1,2".to_string(), Tile::Road);
+        assert!(decode_tiles(&sent).unwrap_err().contains("'1,2'"));
+    }
+
+    #[test]
+    fn newer_server_messages_parse_as_unknown_and_bad_known_ones_fail() {
+        let sent = ServerMessage::Connected { player_id: "p1".to_string() };
+        let parsed = ServerMessage::parse(&serde_json::to_string(&sent).unwrap()).unwrap();
+        assert!(matches!(parsed, ServerMessage::Connected { player_id } if player_id == "p1"));
+
+        let unit = ServerMessage::parse(r#""Shutdown""#).unwrap();
+        assert!(matches!(unit, ServerMessage::Unknown { kind } if kind == "Shutdown"));
+        let with_fields = ServerMessage::parse(r#"{"Weather":{"rain":true}}"#).unwrap();
+        assert!(matches!(with_fields, ServerMessage::Unknown { kind } if kind == "Weather"));
+
+        // A variant we know with the wrong fields is still an error, not `Unknown`
+        assert!(ServerMessage::parse(r#"{"PlayerLeft":{"id":"p1"}}"#).is_err());
+        assert!(ServerMessage::parse("not json").is_err());
+    }
+}
@@ -1,41 +1,184 @@
-use std::collections::HashMap;
 use noise::{NoiseFn, Perlin};
+use super::constants::GameConstants;
 
 // Import types directly to avoid circular dependency
+// Each tile has a fixed two letter wire code so renaming a variant can't break
+// the protocol or saved worlds; the aliases still read data written with variant names
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum Tile {
+    #[serde(rename = "fl", alias = "Floor")]
     Floor,
+    #[serde(rename = "wa", alias = "Wall")]
     Wall,
+    // Void with nothing in it: impassable and drawn blank. Generators never
+    // place it; it only fills gaps, e.g. unset tiles in a saved chunk
+    #[serde(rename = "em", alias = "Empty")]
     Empty,
+    #[serde(rename = "do", alias = "Door")]
     Door,
     // Overworld tiles
+    #[serde(rename = "gr", alias = "Grass")]
     Grass,
+    #[serde(rename = "tr", alias = "Tree")]
     Tree,
+    #[serde(rename = "mo", alias = "Mountain")]
     Mountain,
+    #[serde(rename = "wt", alias = "Water")]
     Water,
+    #[serde(rename = "rd", alias = "Road")]
     Road,
+    #[serde(rename = "vi", alias = "Village")]
     Village,
+    #[serde(rename = "de", alias = "DungeonEntrance")]
     DungeonEntrance,
+    #[serde(rename = "to", alias = "Tower")]
+    Tower, // Landmark
+    #[serde(rename = "ru", alias = "Ruins")]
+    Ruins, // Landmark
     // Dungeon tiles
+    #[serde(rename = "dx", alias = "DungeonExit")]
     DungeonExit,
+    // Extra exit deep inside some dungeons, leading back to the same entrance
+    #[serde(rename = "ds", alias = "DungeonShortcut")]
+    DungeonShortcut,
+}
+
+impl Tile {
+    /// Every tile type, in legend order
+    pub const ALL: [Tile; 15] = [
+        Tile::Floor,
+        Tile::Wall,
+        Tile::Empty,
+        Tile::Door,
+        Tile::Grass,
+        Tile::Tree,
+        Tile::Mountain,
+        Tile::Water,
+        Tile::Road,
+        Tile::Village,
+        Tile::DungeonEntrance,
+        Tile::Tower,
+        Tile::Ruins,
+        Tile::DungeonExit,
+        Tile::DungeonShortcut,
+    ];
+
+    /// Human readable name shown in the help legend
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tile::Floor => "Floor",
+            Tile::Wall => "Wall",
+            Tile::Empty => "Empty",
+            Tile::Door => "Door",
+            Tile::Grass => "Grass",
+            Tile::Tree => "Tree",
+            Tile::Mountain => "Mountain",
+            Tile::Water => "Water",
+            Tile::Road => "Road",
+            Tile::Village => "Village",
+            Tile::DungeonEntrance => "Dungeon Entrance",
+            Tile::Tower => "Tower",
+            Tile::Ruins => "Ruins",
+            Tile::DungeonExit => "Dungeon Exit",
+            Tile::DungeonShortcut => "Shortcut Exit",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct GameMap {
     pub width: i32,
     pub height: i32,
-    pub tiles: HashMap<(i32, i32), Tile>,
+    pub tiles: TileGrid,
+}
+
+/// The tiles of a fixed-size map, stored row by row so a lookup is an index
+/// rather than a hash. Positions outside the grid have no tile.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TileGrid {
+    width: i32,
+    height: i32,
+    cells: Vec<Option<Tile>>,
+}
+
+impl TileGrid {
+    pub fn new(width: i32, height: i32) -> Self {
+        let (width, height) = (width.max(0), height.max(0));
+        TileGrid { width, height, cells: vec![None; (width * height) as usize] }
+    }
+
+    fn index(&self, (x, y): (i32, i32)) -> Option<usize> {
+        (x >= 0 && y >= 0 && x < self.width && y < self.height).then(|| (y * self.width + x) as usize)
+    }
+
+    pub fn get(&self, position: &(i32, i32)) -> Option<&Tile> {
+        self.index(*position).and_then(|index| self.cells[index].as_ref())
+    }
+
+    /// Set a tile, returning the one it replaced. Panics outside the grid.
+    pub fn insert(&mut self, position: (i32, i32), tile: Tile) -> Option<Tile> {
+        let index = self.index(position).unwrap_or_else(|| {
+            panic!("tile {:?} is outside the {}x{} map", position, self.width, self.height)
+        });
+        self.cells[index].replace(tile)
+    }
+
+    /// Every filled-in tile with its position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), Tile)> + '_ {
+        let width = self.width.max(1);
+        self.cells.iter().enumerate().filter_map(move |(index, tile)| {
+            tile.map(|tile| (((index as i32) % width, (index as i32) / width), tile))
+        })
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.iter().map(|(position, _)| position)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = Tile> + '_ {
+        self.cells.iter().flatten().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(Option::is_none)
+    }
+}
+
+impl GameMap {
+    /// A map of the given size with no tiles filled in yet
+    pub fn new(width: i32, height: i32) -> Self {
+        GameMap {
+            width,
+            height,
+            tiles: TileGrid::new(width, height),
+        }
+    }
+
+    /// No map at all, e.g. while the overworld is drawn from chunks instead
+    pub fn empty() -> Self {
+        Self::new(0, 0)
+    }
+
+    /// Whether a position lies inside the map's width and height
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    /// The tile at a position, or None outside the map
+    pub fn tile_at(&self, x: i32, y: i32) -> Option<Tile> {
+        self.tiles.get(&(x, y)).copied()
+    }
 }
 
 pub struct TerrainGenerator;
 
 impl TerrainGenerator {
     pub fn generate_overworld(width: i32, height: i32) -> GameMap {
-        let mut game_map = GameMap {
-            width,
-            height,
-            tiles: HashMap::new(),
-        };
+        let mut game_map = GameMap::new(width, height);
         
         // Create noise generators with different seeds for various terrain features
         let elevation_noise = Perlin::new(42);
@@ -64,35 +207,21 @@ impl TerrainGenerator {
         game_map
     }
     
-    pub fn generate_dungeon(width: i32, height: i32) -> GameMap {
-        let mut game_map = GameMap {
-            width,
-            height,
-            tiles: HashMap::new(),
-        };
-        
-        // Use a random seed based on current time for variety
-        let seed = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as u32;
+    pub fn generate_dungeon_with_seed(width: i32, height: i32, seed: u32) -> GameMap {
+        let mut game_map = GameMap::new(width, height);
         
         // Use a new procedural dungeon generation system with rooms and corridors
-        Self::generate_procedural_dungeon(&mut game_map, seed);
+        Self::generate_procedural_dungeon(&mut game_map, seed, 1);
         
         game_map
     }
 
-    pub fn generate_dungeon_with_seed(width: i32, height: i32, seed: u32) -> GameMap {
-        let mut game_map = GameMap {
-            width,
-            height,
-            tiles: HashMap::new(),
-        };
-        
-        // Use a new procedural dungeon generation system with rooms and corridors
-        Self::generate_procedural_dungeon(&mut game_map, seed);
-        
+    /// Seeded dungeon whose layout grows with the difficulty tier (1 = easiest)
+    pub fn generate_dungeon_with_difficulty(width: i32, height: i32, seed: u32, difficulty: u32) -> GameMap {
+        let mut game_map = GameMap::new(width, height);
+
+        Self::generate_procedural_dungeon(&mut game_map, seed, difficulty);
+
         game_map
     }
     
@@ -277,10 +406,9 @@ impl TerrainGenerator {
             let mut closest_dist = f32::MAX;
             
             // Find closest other location
-            for j in 0..important_locations.len() {
+            for (j, &(x2, y2)) in important_locations.iter().enumerate() {
                 if i == j { continue; }
                 
-                let (x2, y2) = important_locations[j];
                 let dist = ((x2 - x1).pow(2) + (y2 - y1).pow(2)) as f32;
                 
                 if dist < closest_dist {
@@ -333,6 +461,7 @@ impl TerrainGenerator {
         }
     }
     
+    #[allow(dead_code)]
     fn generate_cave_dungeon(game_map: &mut GameMap) {
         // Initialize with random walls and floors
         let wall_chance = 0.4;
@@ -359,7 +488,7 @@ impl TerrainGenerator {
 
         // Apply cellular automata to create natural cave shapes
         for _ in 0..4 { // 4 iterations of smoothing
-            let mut new_tiles = HashMap::new();
+            let mut new_tiles = TileGrid::new(game_map.width, game_map.height);
             
             for x in 0..game_map.width {
                 for y in 0..game_map.height {
@@ -400,7 +529,7 @@ impl TerrainGenerator {
         }
     }
 
-    fn generate_procedural_dungeon(game_map: &mut GameMap, seed: u32) {
+    fn generate_procedural_dungeon(game_map: &mut GameMap, seed: u32, difficulty: u32) {
         // Initialize entire dungeon with walls
         for x in 0..game_map.width {
             for y in 0..game_map.height {
@@ -411,8 +540,11 @@ impl TerrainGenerator {
         // Define room generation parameters
         let min_room_size = 4;
         let max_room_size = 8;
-        let max_rooms = 8;
+        // Deeper tiers pack in more rooms
+        let extra_tiers = difficulty.clamp(1, GameConstants::MAX_DUNGEON_DIFFICULTY) as i32 - 1;
+        let max_rooms = 8 + extra_tiers * GameConstants::EXTRA_ROOMS_PER_DIFFICULTY;
         let mut rooms = Vec::new();
+        let corridor_width = Self::corridor_width(seed);
         let mut rng_seed = seed; // Use the provided seed instead of fixed 42
 
         // Generate a helper function for pseudo-random numbers
@@ -434,6 +566,7 @@ impl TerrainGenerator {
                 y: room_y,
                 width: room_width,
                 height: room_height,
+                room_type: RoomType::for_room(seed, rooms.len()),
             };
 
             // Check if room overlaps with existing ones
@@ -454,7 +587,8 @@ impl TerrainGenerator {
                     let prev_room = &rooms[rooms.len() - 1];
                     Self::create_corridor(game_map, 
                         Self::room_center(&new_room), 
-                        Self::room_center(prev_room)
+                        Self::room_center(prev_room),
+                        corridor_width,
                     );
                 }
                 
@@ -469,6 +603,7 @@ impl TerrainGenerator {
                 y: 2,
                 width: 6,
                 height: 4,
+                room_type: RoomType::Rectangle,
             };
             Self::create_room(game_map, &fallback_room);
             rooms.push(fallback_room);
@@ -479,6 +614,8 @@ impl TerrainGenerator {
 
         // Ensure spawn position is on a floor tile
         Self::ensure_safe_spawn_position(game_map, &rooms);
+
+        Self::add_shortcut_exits(game_map, &rooms, Self::shortcut_count(seed, rooms.len()));
     }
 
     fn rooms_overlap(room1: &Room, room2: &Room) -> bool {
@@ -488,10 +625,12 @@ impl TerrainGenerator {
         room1.y + room1.height > room2.y
     }
 
+    /// Carve the room's floor. Only floor is ever added, so corridors that
+    /// already cross the room's area stay open whatever its shape.
     fn create_room(game_map: &mut GameMap, room: &Room) {
         for x in room.x..room.x + room.width {
             for y in room.y..room.y + room.height {
-                if x > 0 && x < game_map.width - 1 && y > 0 && y < game_map.height - 1 {
+                if x > 0 && x < game_map.width - 1 && y > 0 && y < game_map.height - 1 && room.is_floor(x, y) {
                     game_map.tiles.insert((x, y), Tile::Floor);
                 }
             }
@@ -502,37 +641,78 @@ impl TerrainGenerator {
         (room.x + room.width / 2, room.y + room.height / 2)
     }
 
-    fn create_corridor(game_map: &mut GameMap, start: (i32, i32), end: (i32, i32)) {
+    /// Some dungeons get 2-wide corridors. Like the other per-dungeon features,
+    /// this hashes the seed rather than drawing from the layout's random
+    /// sequence, so room positions stay the same for a given seed
+    fn corridor_width(seed: u32) -> i32 {
+        let roll = seed.wrapping_mul(2654435761).rotate_right(16) % 100;
+        if roll < GameConstants::WIDE_CORRIDOR_PERCENT { 2 } else { 1 }
+    }
+
+    /// How many shortcut exits a dungeon gets. The seed is salted so this roll
+    /// doesn't repeat `RoomType::for_room`'s for the first room
+    fn shortcut_count(seed: u32, room_count: usize) -> usize {
+        if room_count < GameConstants::SHORTCUT_MIN_ROOMS {
+            return 0;
+        }
+        let roll = (seed ^ 0x9e3779b9).wrapping_mul(2246822519).rotate_right(13) % 100;
+        if roll < GameConstants::SHORTCUT_PERCENT {
+            1 + (roll as usize % GameConstants::MAX_DUNGEON_SHORTCUTS)
+        } else {
+            0
+        }
+    }
+
+    /// Put shortcut exits at the centers of the rooms farthest from the spawn room
+    fn add_shortcut_exits(game_map: &mut GameMap, rooms: &[Room], count: usize) {
+        let Some((spawn_room, others)) = rooms.split_first() else {
+            return;
+        };
+        let (spawn_x, spawn_y) = Self::room_center(spawn_room);
+        let mut deepest: Vec<(i32, i32)> = others.iter().map(Self::room_center).collect();
+        deepest.sort_by_key(|&(x, y)| std::cmp::Reverse((x - spawn_x).abs() + (y - spawn_y).abs()));
+
+        for position in deepest.into_iter().take(count) {
+            game_map.tiles.insert(position, Tile::DungeonShortcut);
+        }
+    }
+
+    fn create_corridor(game_map: &mut GameMap, start: (i32, i32), end: (i32, i32), width: i32) {
         let (mut x, mut y) = start;
         let (target_x, target_y) = end;
 
+        // Extra width goes below horizontal runs and right of vertical ones, so
+        // the single tile path between the room centers is always carved
+        let mut carve = |x: i32, y: i32, (extra_x, extra_y): (i32, i32)| {
+            for step in 0..width {
+                let (x, y) = (x + extra_x * step, y + extra_y * step);
+                if x > 0 && x < game_map.width - 1 && y > 0 && y < game_map.height - 1 {
+                    game_map.tiles.insert((x, y), Tile::Floor);
+                }
+            }
+        };
+
         // Create L-shaped corridor
         // First move horizontally
         while x != target_x {
-            if x > 0 && x < game_map.width - 1 && y > 0 && y < game_map.height - 1 {
-                game_map.tiles.insert((x, y), Tile::Floor);
-            }
+            carve(x, y, (0, 1));
             x += if target_x > x { 1 } else { -1 };
         }
 
         // Then move vertically
         while y != target_y {
-            if x > 0 && x < game_map.width - 1 && y > 0 && y < game_map.height - 1 {
-                game_map.tiles.insert((x, y), Tile::Floor);
-            }
+            carve(x, y, (1, 0));
             y += if target_y > y { 1 } else { -1 };
         }
 
         // Ensure the endpoint is also a floor
-        if x > 0 && x < game_map.width - 1 && y > 0 && y < game_map.height - 1 {
-            game_map.tiles.insert((x, y), Tile::Floor);
-        }
+        carve(x, y, (1, 0));
     }
 
     fn add_doors_to_rooms(game_map: &mut GameMap, rooms: &[Room], next_random: &mut impl FnMut() -> u32) {
         for room in rooms {
             // Add doors on room perimeter (sometimes)
-            if next_random() % 3 == 0 { // 33% chance of door
+            if next_random().is_multiple_of(3) { // 33% chance of door
                 // Pick a random wall position
                 let side = next_random() % 4;
                 let (door_x, door_y) = match side {
@@ -544,19 +724,18 @@ impl TerrainGenerator {
 
                 // Only place door if it's adjacent to a floor tile and on a wall
                 if door_x > 0 && door_x < game_map.width - 1 && 
-                   door_y > 0 && door_y < game_map.height - 1 {
-                    if game_map.tiles.get(&(door_x, door_y)) == Some(&Tile::Wall) {
-                        // Check if there's a floor tile nearby (indicating a corridor)
-                        let has_floor_neighbor = [
-                            (door_x - 1, door_y), (door_x + 1, door_y),
-                            (door_x, door_y - 1), (door_x, door_y + 1)
-                        ].iter().any(|(x, y)| 
-                            game_map.tiles.get(&(*x, *y)) == Some(&Tile::Floor)
-                        );
-
-                        if has_floor_neighbor {
-                            game_map.tiles.insert((door_x, door_y), Tile::Door);
-                        }
+                   door_y > 0 && door_y < game_map.height - 1 &&
+                   game_map.tiles.get(&(door_x, door_y)) == Some(&Tile::Wall) {
+                    // Check if there's a floor tile nearby (indicating a corridor)
+                    let has_floor_neighbor = [
+                        (door_x - 1, door_y), (door_x + 1, door_y),
+                        (door_x, door_y - 1), (door_x, door_y + 1)
+                    ].iter().any(|(x, y)| 
+                        game_map.tiles.get(&(*x, *y)) == Some(&Tile::Floor)
+                    );
+
+                    if has_floor_neighbor {
+                        game_map.tiles.insert((door_x, door_y), Tile::Door);
                     }
                 }
             }
@@ -614,4 +793,240 @@ struct Room {
     y: i32,
     width: i32,
     height: i32,
+    room_type: RoomType,
+}
+
+impl Room {
+    /// Whether a position inside the room's bounds is floor for its shape.
+    /// Every shape keeps its center open for corridors and the dungeon exit.
+    fn is_floor(&self, x: i32, y: i32) -> bool {
+        let (center_x, center_y) = TerrainGenerator::room_center(self);
+        let (dx, dy) = (x - center_x, y - center_y);
+        match self.room_type {
+            RoomType::Rectangle => true,
+            // Lone pillars on every other tile away from the room's edge
+            RoomType::Pillared => {
+                let (local_x, local_y) = (x - self.x, y - self.y);
+                let inner = local_x > 0 && local_y > 0 && local_x < self.width - 1 && local_y < self.height - 1;
+                !(inner && local_x % 2 == 1 && local_y % 2 == 1 && (dx, dy) != (0, 0))
+            }
+            // An ellipse filling the room's bounds
+            RoomType::Circular => {
+                let radius_x = self.width as f64 / 2.0;
+                let radius_y = self.height as f64 / 2.0;
+                let nx = (x as f64 + 0.5 - (self.x as f64 + radius_x)) / radius_x;
+                let ny = (y as f64 + 0.5 - (self.y as f64 + radius_y)) / radius_y;
+                nx * nx + ny * ny <= 1.0 || (dx, dy) == (0, 0)
+            }
+            // Two bars through the center, half the room wide and tall
+            RoomType::Cross => {
+                dx.abs() <= (self.width / 4).max(1) || dy.abs() <= (self.height / 4).max(1)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RoomType {
+    Rectangle,
+    Pillared,
+    Circular,
+    Cross,
+}
+
+impl RoomType {
+    /// Picked from the seed and room index rather than the layout's random
+    /// sequence, so room positions stay the same for a given seed
+    fn for_room(seed: u32, index: usize) -> Self {
+        let roll = seed.wrapping_add(index as u32).wrapping_mul(2246822519).rotate_right(13) % 10;
+        match roll {
+            0..=4 => RoomType::Rectangle,
+            5..=6 => RoomType::Pillared,
+            7..=8 => RoomType::Circular,
+            _ => RoomType::Cross,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::game_logic::GameLogic;
+    use std::collections::HashSet;
+
+    // Changing any of these codes breaks compatibility with older clients and saved worlds
+    const WIRE_CODES: [(Tile, &str); 15] = [
+        (Tile::Floor, "fl"),
+        (Tile::Wall, "wa"),
+        (Tile::Empty, "em"),
+        (Tile::Door, "do"),
+        (Tile::Grass, "gr"),
+        (Tile::Tree, "tr"),
+        (Tile::Mountain, "mo"),
+        (Tile::Water, "wt"),
+        (Tile::Road, "rd"),
+        (Tile::Village, "vi"),
+        (Tile::DungeonEntrance, "de"),
+        (Tile::Tower, "to"),
+        (Tile::Ruins, "ru"),
+        (Tile::DungeonExit, "dx"),
+        (Tile::DungeonShortcut, "ds"),
+    ];
+
+    #[test]
+    fn tiles_serialize_to_stable_wire_codes() {
+        assert_eq!(WIRE_CODES.len(), Tile::ALL.len());
+        for (tile, code) in WIRE_CODES {
+            let json = serde_json::to_string(&tile).unwrap();
+            assert_eq!(json, format!("\"{}\"", code));
+            assert_eq!(serde_json::from_str::<Tile>(&json).unwrap(), tile);
+        }
+    }
+
+    #[test]
+    fn tile_grids_only_hold_tiles_inside_the_map() {
+        let mut grid = TileGrid::new(4, 3);
+        assert!(grid.is_empty());
+        assert_eq!(grid.insert((3, 2), Tile::Wall), None);
+        assert_eq!(grid.insert((3, 2), Tile::Floor), Some(Tile::Wall));
+        grid.insert((0, 1), Tile::Door);
+
+        assert_eq!(grid.get(&(3, 2)), Some(&Tile::Floor));
+        assert_eq!(grid.get(&(1, 1)), None);
+        for outside in [(-1, 0), (4, 0), (0, 3), (i32::MIN, i32::MAX)] {
+            assert_eq!(grid.get(&outside), None);
+        }
+        assert_eq!(grid.iter().collect::<Vec<_>>(), vec![((0, 1), Tile::Door), ((3, 2), Tile::Floor)]);
+        assert_eq!(grid.len(), 2);
+    }
+
+    /// Times the lookups a viewport makes over a dungeon, dense grid against the
+    /// HashMap it replaced. Run with
+    /// `cargo test --release --lib dungeon_lookup_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn dungeon_lookup_benchmark() {
+        use std::collections::HashMap;
+        use std::hint::black_box;
+        use std::time::Instant;
+
+        let dungeon = TerrainGenerator::generate_dungeon_with_difficulty(
+            GameConstants::DUNGEON_WIDTH, GameConstants::DUNGEON_HEIGHT, 7, 3,
+        );
+        let hashed: HashMap<(i32, i32), Tile> = dungeon.tiles.iter().collect();
+        // A view reaching past the edges, like the renderer's around a player near a wall
+        let view = |lookup: &dyn Fn(i32, i32) -> Option<Tile>| {
+            let mut walls = 0;
+            for y in -10..dungeon.height + 10 {
+                for x in -20..dungeon.width + 20 {
+                    walls += (lookup(black_box(x), black_box(y)) == Some(Tile::Wall)) as u32;
+                }
+            }
+            walls
+        };
+        let time = |name: &str, lookup: &dyn Fn(i32, i32) -> Option<Tile>| {
+            let start = Instant::now();
+            let walls: u32 = (0..10_000).map(|_| view(lookup)).sum();
+            println!("{}: {:?} per view ({} walls)", name, start.elapsed() / 10_000, walls / 10_000);
+        };
+        time("HashMap", &|x, y| hashed.get(&(x, y)).copied());
+        time("TileGrid", &|x, y| dungeon.tile_at(x, y));
+    }
+
+    fn reachable_from_exit(dungeon: &GameMap) -> HashSet<(i32, i32)> {
+        let exit = dungeon.tiles.iter()
+            .find(|&(_, tile)| tile == Tile::DungeonExit)
+            .map(|(pos, _)| pos)
+            .expect("dungeon has an exit");
+        let mut reached = HashSet::from([exit]);
+        let mut frontier = vec![exit];
+        while let Some((x, y)) = frontier.pop() {
+            for next in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                let walkable = dungeon.tiles.get(&next)
+                    .is_some_and(|&tile| tile != Tile::Wall && tile != Tile::Empty);
+                if walkable && reached.insert(next) {
+                    frontier.push(next);
+                }
+            }
+        }
+        reached
+    }
+
+    #[test]
+    fn corridor_widths_vary_and_every_room_stays_connected() {
+        let widths: HashSet<i32> = (0..200).map(TerrainGenerator::corridor_width).collect();
+        assert_eq!(widths, HashSet::from([1, 2]));
+
+        for seed in 0..40 {
+            let dungeon = TerrainGenerator::generate_dungeon_with_difficulty(50, 30, seed, 1);
+            let reachable = reachable_from_exit(&dungeon);
+            for (pos, tile) in dungeon.tiles.iter() {
+                if tile != Tile::Wall {
+                    assert!(reachable.contains(&pos), "{:?} at {:?} cut off in dungeon {}", tile, pos, seed);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn some_dungeons_get_reachable_shortcuts_away_from_the_exit() {
+        let mut with_shortcuts = 0;
+        for seed in 0..40 {
+            let dungeon = TerrainGenerator::generate_dungeon_with_difficulty(40, 20, seed, 3);
+            let exit = GameLogic::get_safe_dungeon_spawn_position(&dungeon);
+            assert_eq!(dungeon.tiles.get(&exit), Some(&Tile::DungeonExit));
+
+            let reachable = reachable_from_exit(&dungeon);
+            let shortcuts: Vec<_> = dungeon.tiles.iter()
+                .filter(|&(_, tile)| tile == Tile::DungeonShortcut)
+                .map(|(pos, _)| pos)
+                .collect();
+            assert!(shortcuts.len() <= GameConstants::MAX_DUNGEON_SHORTCUTS);
+            for pos in &shortcuts {
+                assert!(reachable.contains(pos), "shortcut at {:?} cut off in dungeon {}", pos, seed);
+                assert!(GameLogic::is_at_dungeon_exit(&dungeon, pos.0, pos.1));
+            }
+            if !shortcuts.is_empty() {
+                with_shortcuts += 1;
+            }
+        }
+        assert!(with_shortcuts > 0 && with_shortcuts < 40);
+    }
+
+    #[test]
+    fn shortcut_counts_dont_follow_the_first_room_shape() {
+        for room_type in [RoomType::Rectangle, RoomType::Pillared, RoomType::Circular, RoomType::Cross] {
+            let counts: HashSet<usize> = (0..2000)
+                .filter(|&seed| RoomType::for_room(seed, 0) == room_type)
+                .map(|seed| TerrainGenerator::shortcut_count(seed, GameConstants::SHORTCUT_MIN_ROOMS))
+                .collect();
+            assert_eq!(counts, HashSet::from([0, 1, 2]), "{:?} first rooms", room_type);
+        }
+    }
+
+    #[test]
+    fn room_shapes_keep_their_center_open() {
+        for room_type in [RoomType::Rectangle, RoomType::Pillared, RoomType::Circular, RoomType::Cross] {
+            for (width, height) in [(4, 4), (5, 7), (8, 8)] {
+                let room = Room { x: 3, y: 3, width, height, room_type };
+                let (center_x, center_y) = TerrainGenerator::room_center(&room);
+                assert!(room.is_floor(center_x, center_y), "{:?} {}x{} has a solid center", room_type, width, height);
+            }
+        }
+
+        let pillared = Room { x: 0, y: 0, width: 8, height: 8, room_type: RoomType::Pillared };
+        assert!(!pillared.is_floor(1, 1));
+        assert!(pillared.is_floor(0, 1) && pillared.is_floor(2, 1));
+        let cross = Room { x: 0, y: 0, width: 8, height: 8, room_type: RoomType::Cross };
+        assert!(!cross.is_floor(0, 0));
+        assert!(cross.is_floor(4, 0) && cross.is_floor(0, 4));
+    }
+
+    #[test]
+    fn tiles_still_read_variant_names() {
+        for tile in Tile::ALL {
+            let legacy = format!("\"{:?}\"", tile);
+            assert_eq!(serde_json::from_str::<Tile>(&legacy).unwrap(), tile);
+        }
+    }
 }
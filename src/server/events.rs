@@ -0,0 +1,40 @@
+// Events the game rules emit, kept apart from how they reach the clients
+use rust_cli_roguelike::common::game_logic::Tile;
+use rust_cli_roguelike::common::protocol::{GameState, NetworkGameMap, PlayerId, ServerMessage};
+
+/// Who an event's message goes to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Audience {
+    Player(PlayerId),
+    Everyone,
+}
+
+/// Something that happened in a room. Game logic queues these and the server
+/// turns them into `ServerMessage`s once the client message is handled.
+#[derive(Debug, Clone)]
+pub enum GameEvent {
+    PlayerMoved { player_id: PlayerId, x: i32, y: i32 },
+    EnteredDungeon { player_id: PlayerId, dungeon_map: NetworkGameMap },
+    TileChanged { x: i32, y: i32, tile: Tile },
+    // Players' positions, maps or inventories changed
+    StateChanged,
+    Notice { audience: Audience, text: String },
+}
+
+impl GameEvent {
+    /// The message clients get for this event; `snapshot` builds the current
+    /// game state and is only called for events that need it
+    pub fn into_message(self, snapshot: impl FnOnce() -> GameState) -> (Audience, ServerMessage) {
+        match self {
+            GameEvent::PlayerMoved { player_id, x, y } => {
+                (Audience::Everyone, ServerMessage::PlayerMoved { player_id, x, y })
+            }
+            GameEvent::EnteredDungeon { player_id, dungeon_map } => {
+                (Audience::Player(player_id), ServerMessage::DungeonData { dungeon_map })
+            }
+            GameEvent::TileChanged { x, y, tile } => (Audience::Everyone, ServerMessage::TileChanged { x, y, tile }),
+            GameEvent::StateChanged => (Audience::Everyone, ServerMessage::GameState { state: snapshot() }),
+            GameEvent::Notice { audience, text } => (audience, ServerMessage::Message { text }),
+        }
+    }
+}
@@ -1,5 +1,9 @@
-use std::collections::HashMap;
+mod events;
+mod recording;
+
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{Mutex, mpsc};
 use tokio_tungstenite::{accept_async, tungstenite::Message};
@@ -8,74 +12,261 @@ use uuid::Uuid;
 
 use rust_cli_roguelike::common::protocol::{
     ClientMessage, ServerMessage, GameState, NetworkPlayer, ChunkData,
-    NetworkCurrentScreen, PlayerId, MapType
+    NetworkCurrentScreen, PlayerId, MapType, TileModification, LanAnnouncement, encode_tiles
 };
 use rust_cli_roguelike::common::game_logic::{GameLogic, Tile, GameChunkManager, GameMap};
-use rust_cli_roguelike::common::chunk::CHUNK_SIZE;
+use rust_cli_roguelike::common::chunk::{BiomeConfig, ChunkSettings, InfiniteTerrainGenerator, CHUNK_SIZE};
+use rust_cli_roguelike::common::constants::{Difficulty, GameConstants};
+use rust_cli_roguelike::common::item::Item;
+
+use events::{Audience, GameEvent};
+use recording::{Recorder, SharedRecorder};
 
 type SharedGameState = Arc<Mutex<ServerGameState>>;
+type SharedRooms = Arc<Mutex<Rooms>>;
 type ClientSender = mpsc::UnboundedSender<ServerMessage>;
 type ClientReceiver = mpsc::UnboundedReceiver<ServerMessage>;
 
-// Player color palette - distinct colors for multiplayer
-const PLAYER_COLORS: [(u8, u8, u8); 10] = [
-    (255, 69, 0),   // Red-Orange
-    (50, 205, 50),  // Lime Green
-    (30, 144, 255), // Dodger Blue
-    (255, 20, 147), // Deep Pink
-    (255, 215, 0),  // Gold
-    (138, 43, 226), // Blue Violet
-    (0, 255, 255),  // Cyan
-    (255, 165, 0),  // Orange
-    (124, 252, 0),  // Lawn Green
-    (255, 105, 180),// Hot Pink
-];
+// Player colors are spread around the hue wheel by the golden angle, so any
+// number of players get distinct colors and neighbours in join order contrast well
+const GOLDEN_ANGLE_DEGREES: f32 = 137.507_77;
+// Hues too close to the local player's yellow '@' are skipped
+const YELLOW_HUE_BAND: (f32, f32) = (40.0, 80.0);
+
+/// The `index`th player color: bright and saturated so it reads on a dark terminal
+fn player_color(index: usize) -> (u8, u8, u8) {
+    let (band_start, band_end) = YELLOW_HUE_BAND;
+    let usable_range = 360.0 - (band_end - band_start);
+    let mut hue = (index as f32 * GOLDEN_ANGLE_DEGREES) % 360.0 / 360.0 * usable_range;
+    if hue >= band_start {
+        hue += band_end - band_start;
+    }
+    hsv_to_rgb(hue, 0.8, 1.0)
+}
+
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> (u8, u8, u8) {
+    let chroma = value * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    let channel = |c: f32| ((c + m) * 255.0).round() as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+/// Independent worlds hosted by this server, keyed by room code
+#[derive(Debug, Default)]
+struct Rooms {
+    worlds: HashMap<String, SharedGameState>,
+    members: HashMap<String, usize>, // Players holding each room, counted from join() until leave()
+    recorder: Option<SharedRecorder>, // Shared by every room when recording
+    max_players: Option<usize>, // Across all rooms; unlimited when None
+    players_connected: usize,
+    spawn: Option<(i32, i32)>, // Where new players start looking for a free tile; the default spawn when None
+    difficulty: Difficulty, // Preset for every room
+    biomes: BiomeConfig, // Terrain thresholds for every room's overworld
+    persistent: bool, // Save modified chunks under worlds/; rooms only live in memory otherwise
+}
+
+impl Rooms {
+    /// Take a player slot and return the room's world, or an error if the server is full
+    fn join(&mut self, room: &str) -> Result<SharedGameState, String> {
+        if let Some(max_players) = self.max_players {
+            if self.players_connected >= max_players {
+                return Err(format!("The server is full ({} players). Try again later.", max_players));
+            }
+        }
+        self.players_connected += 1;
+        // Counted now rather than once the player is added to the room's state, so
+        // the room can't close (and be recreated) while they are still connecting
+        *self.members.entry(room.to_string()).or_default() += 1;
+        Ok(self.get_or_create(room))
+    }
+
+    /// Free a player's slot and close their room if they were the last one in it
+    async fn leave(&mut self, room: &str) {
+        self.players_connected = self.players_connected.saturating_sub(1);
+        if let Some(members) = self.members.get_mut(room) {
+            *members = members.saturating_sub(1);
+        }
+        self.close_if_empty(room).await;
+    }
+
+    fn get_or_create(&mut self, room: &str) -> SharedGameState {
+        if let Some(world) = self.worlds.get(room) {
+            return Arc::clone(world);
+        }
+        log::info!("Creating room '{}'", room);
+        let world = Arc::new(Mutex::new(self.new_world(room)));
+        self.worlds.insert(room.to_string(), Arc::clone(&world));
+        world
+    }
+
+    /// A room's world set up with the server's options
+    fn new_world(&self, room: &str) -> ServerGameState {
+        let mut state = if self.persistent { ServerGameState::persistent(room) } else { ServerGameState::new(room) };
+        state.recorder = self.recorder.clone();
+        if let Some(spawn) = self.spawn {
+            state.spawn = spawn;
+        }
+        state.difficulty = self.difficulty;
+        state.chunk_manager.set_biomes(self.biomes);
+        state
+    }
+
+    /// Drop a room nobody is in any more, keeping its world changes on disk.
+    /// The default room always stays open.
+    async fn close_if_empty(&mut self, room: &str) {
+        if room == GameConstants::DEFAULT_ROOM || self.members.get(room).is_some_and(|&members| members > 0) {
+            return;
+        }
+
+        self.members.remove(room);
+        if let Some(world) = self.worlds.get(room) {
+            let mut state = world.lock().await;
+            if let Err(e) = state.chunk_manager.save_dirty_chunks() {
+                log::error!("Failed to save world for room '{}': {}", room, e);
+            }
+        }
+
+        if self.worlds.remove(room).is_some() {
+            log::info!("Closing empty room '{}'", room);
+        }
+    }
+
+    /// Write every room's modified chunks, e.g. before the server shuts down
+    async fn save_all(&self) {
+        for (room, world) in &self.worlds {
+            if let Err(e) = world.lock().await.chunk_manager.save_dirty_chunks() {
+                log::error!("Failed to save world for room '{}': {}", room, e);
+            }
+        }
+    }
+
+    /// Log player, dungeon and chunk counts across all rooms, plus the message
+    /// rate since the previous call `interval` ago
+    async fn log_stats(&self, interval: Duration) {
+        let (mut players, mut dungeons, mut chunks_generated, mut messages) = (0, 0, 0, 0);
+        for world in self.worlds.values() {
+            let mut state = world.lock().await;
+            players += state.players.len();
+            dungeons += state.dungeons.len();
+            chunks_generated += state.chunk_manager.chunks_generated();
+            messages += std::mem::take(&mut state.messages_since_stats);
+        }
+
+        log::info!(
+            "Stats: {} rooms, {} players, {} dungeons loaded, {} chunks generated, {:.1} messages/sec",
+            self.worlds.len(),
+            players,
+            dungeons,
+            chunks_generated,
+            messages as f64 / interval.as_secs_f64(),
+        );
+    }
+}
 
 #[derive(Debug)]
 struct ServerGameState {
+    room: String,
     players: HashMap<PlayerId, NetworkPlayer>,
     chunk_manager: GameChunkManager,
     turn_count: u32,
     client_senders: HashMap<PlayerId, ClientSender>,
     // Store generated dungeons keyed by entrance coordinates
     dungeons: HashMap<(i32, i32), GameMap>,
+    // Villages each player has visited, the only valid fast travel destinations
+    visited_villages: HashMap<PlayerId, HashSet<(i32, i32)>>,
+    // Ruins whose recall scroll someone already took
+    looted_ruins: HashSet<(i32, i32)>,
+    // Logs every client message when the server runs with --record
+    recorder: Option<SharedRecorder>,
+    // Client messages handled since the last stats log
+    messages_since_stats: u64,
+    // New players spawn on the nearest free walkable tile to this
+    spawn: (i32, i32),
+    // Sets the starting HP of players joining the room
+    difficulty: Difficulty,
+    // Events from the message being handled, sent to clients once it's done
+    events: Vec<GameEvent>,
+    // The room's latest chat, oldest first, for players who join later
+    chat_history: VecDeque<(String, String)>,
     // Note: current_map_type is now per-player, not global
 }
 
 impl ServerGameState {
-    fn new() -> Self {
-        // Create chunk manager with a fixed seed for consistent multiplayer worlds
-        let seed = 12345; // Fixed seed ensures all players see the same world
-        let chunk_manager = GameLogic::create_chunk_manager(seed);
+    /// A room whose world only lives in memory
+    fn new(room: &str) -> Self {
+        // Fixed seed per room ensures all players in the room see the same world
+        let seed = GameLogic::seed_for_room(room);
+        Self::with_chunk_manager(room, GameLogic::create_chunk_manager(seed, ChunkSettings::default()))
+    }
+
+    /// A room that saves its modified chunks and picks them up again when reopened
+    fn persistent(room: &str) -> Self {
+        let seed = GameLogic::seed_for_room(room);
+        let world_dir = GameLogic::world_dir_for_room(room);
+        Self::with_chunk_manager(room, GameChunkManager::with_world_dir(seed, ChunkSettings::default(), world_dir))
+    }
 
+    fn with_chunk_manager(room: &str, chunk_manager: GameChunkManager) -> Self {
         Self {
+            room: room.to_string(),
             players: HashMap::new(),
             chunk_manager,
             turn_count: 0,
             client_senders: HashMap::new(),
             dungeons: HashMap::new(),
+            visited_villages: HashMap::new(),
+            looted_ruins: HashSet::new(),
+            recorder: None,
+            messages_since_stats: 0,
+            spawn: GameLogic::get_overworld_spawn_position(),
+            difficulty: Difficulty::default(),
+            events: Vec::new(),
+            chat_history: VecDeque::new(),
         }
     }
 
+    /// Overworld tiles other players stand on, kept clear when placing `player_id`
+    fn occupied_overworld_tiles(&self, player_id: &PlayerId) -> HashSet<(i32, i32)> {
+        self.players.values()
+            .filter(|player| player.current_map_type == MapType::Overworld && player.id != *player_id)
+            .map(|player| (player.x, player.y))
+            .collect()
+    }
+
     fn add_player(&mut self, player_id: PlayerId, player_name: String, sender: ClientSender) {
-        let (spawn_x, spawn_y) = GameLogic::get_overworld_spawn_position();
+        // Keep clear of everyone already in the overworld so nobody spawns stacked
+        let occupied = self.occupied_overworld_tiles(&player_id);
+        let (spawn_x, spawn_y) = GameLogic::find_free_overworld_spawn(&mut self.chunk_manager, self.spawn, &occupied);
         
-        // Assign a color based on the number of existing players
-        let color_index = self.players.len() % PLAYER_COLORS.len();
-        let color = PLAYER_COLORS[color_index];
+        // Take the first color nobody in the room is using, so leaving players free theirs up
+        let color = (0..)
+            .map(player_color)
+            .find(|color| self.players.values().all(|player| player.color != *color))
+            .expect("the color sequence is unbounded");
         
         let player = NetworkPlayer {
             id: player_id.clone(),
             name: player_name,
             x: spawn_x,
             y: spawn_y,
-            hp: 20,
-            max_hp: 20,
+            hp: self.difficulty.starting_hp(),
+            max_hp: self.difficulty.starting_hp(),
             symbol: '@',
             current_screen: NetworkCurrentScreen::Game,
             color,
             current_map_type: MapType::Overworld, // New players start in overworld
             dungeon_entrance_pos: None, // No dungeon entrance initially
+            inventory: Vec::new(),
         };
 
         self.players.insert(player_id.clone(), player.clone());
@@ -91,6 +282,7 @@ impl ServerGameState {
 
     fn remove_player(&mut self, player_id: &PlayerId) {
         self.players.remove(player_id);
+        self.visited_villages.remove(player_id);
         self.client_senders.remove(player_id);
 
         // Notify all other players
@@ -101,84 +293,124 @@ impl ServerGameState {
     }
 
     fn move_player(&mut self, player_id: &PlayerId, dx: i32, dy: i32) -> Result<(), String> {
+        self.restore_missing_dungeon(player_id);
         if let Some(player) = self.players.get_mut(player_id) {
             let new_x = player.x + dx;
             let new_y = player.y + dy;
             let current_map_type = player.current_map_type;
 
             // Validate movement based on player's current map type
-            let (tile, is_valid) = if current_map_type == MapType::Dungeon {
+            let tile = if current_map_type == MapType::Dungeon {
                 // In dungeons, use the stored dungeon map for proper validation
-                let tile = if let Some((entrance_x, entrance_y)) = player.dungeon_entrance_pos {
-                    let entrance_key = (entrance_x, entrance_y);
-                    if let Some(dungeon_map) = self.dungeons.get(&entrance_key) {
-                        dungeon_map.tiles.get(&(new_x, new_y)).cloned()
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                };
-                
-                let is_valid = tile.map_or(false, |t| GameLogic::is_movement_valid(t));
-                (tile, is_valid)
+                let dungeon_map = player.dungeon_entrance_pos
+                    .and_then(|entrance| self.dungeons.get(&entrance))
+                    .ok_or_else(GameLogic::get_dungeon_edge_message)?;
+                Some(GameLogic::check_dungeon_move(dungeon_map, new_x, new_y)?)
             } else {
                 // In overworld, use chunk manager
                 self.chunk_manager.update_player_position(new_x, new_y);
-                let tile = self.chunk_manager.get_tile(new_x, new_y);
-                let is_valid = tile.map_or(true, |t| GameLogic::is_movement_valid(t));
-                (tile, is_valid)
+                GameLogic::check_overworld_move(&mut self.chunk_manager, new_x, new_y)?
             };
 
-            if is_valid {
-                player.x = new_x;
-                player.y = new_y;
-                self.turn_count += 1;
-
-                // Handle special tile interactions only in overworld
-                if current_map_type == MapType::Overworld {
-                    if let Some(tile) = tile {
-                        if let Some(interaction_message) = GameLogic::get_tile_interaction_message(tile) {
-                            let msg = ServerMessage::Message {
-                                text: interaction_message,
-                            };
-                            // Send to the specific player
-                            if let Some(sender) = self.client_senders.get(player_id) {
-                                let _ = sender.send(msg);
-                            }
-                        }
-                        
-                        // Handle special multiplayer tile interactions - broadcast to all players
-                        if tile == Tile::Village {
-                            let player_name = player.name.clone();
-                            let msg = ServerMessage::Message {
-                                text: format!("{} visits the village.", player_name),
-                            };
-                            self.broadcast_to_all(msg);
-                        }
+            player.x = new_x;
+            player.y = new_y;
+            self.turn_count += 1;
+
+            // Handle special tile interactions only in overworld
+            if current_map_type == MapType::Overworld {
+                if let Some(tile) = tile {
+                    if let Some(interaction_message) = GameLogic::get_tile_interaction_message(tile) {
+                        // Only for the player who moved
+                        self.events.push(GameEvent::Notice {
+                            audience: Audience::Player(player_id.clone()),
+                            text: interaction_message,
+                        });
+                    }
+                    
+                    // Handle special multiplayer tile interactions - broadcast to all players
+                    if tile == Tile::Village {
+                        self.visited_villages.entry(player_id.clone()).or_default().insert((new_x, new_y));
+                        self.events.push(GameEvent::Notice {
+                            audience: Audience::Everyone,
+                            text: format!("{} visits the village.", player.name),
+                        });
+                    } else if tile == Tile::Ruins
+                        && GameLogic::ruins_have_scroll(new_x, new_y)
+                        && self.looted_ruins.insert((new_x, new_y))
+                    {
+                        player.inventory.push(Item::RecallScroll);
+                        self.events.push(GameEvent::Notice {
+                            audience: Audience::Player(player_id.clone()),
+                            text: "You find a recall scroll among the ruins.".to_string(),
+                        });
+                    } else if tile == Tile::Tower && !player.inventory.contains(&Item::Spyglass) {
+                        player.inventory.push(Item::Spyglass);
+                        self.events.push(GameEvent::Notice {
+                            audience: Audience::Player(player_id.clone()),
+                            text: "You find an old spyglass at the top of the tower.".to_string(),
+                        });
                     }
                 }
-
-                // Notify all players about the movement
-                let move_message = ServerMessage::PlayerMoved {
-                    player_id: player_id.clone(),
-                    x: new_x,
-                    y: new_y,
-                };
-                self.broadcast_to_all(move_message);
-
-                // Send updated game state
-                self.broadcast_game_state();
-                Ok(())
-            } else {
-                let tile = tile.unwrap_or(Tile::Wall);
-                Err(GameLogic::get_blocked_movement_message(tile))
             }
+
+            self.events.push(GameEvent::PlayerMoved {
+                player_id: player_id.clone(),
+                x: new_x,
+                y: new_y,
+            });
+            self.events.push(GameEvent::StateChanged);
+            Ok(())
         } else {
             Err("Player not found.".to_string())
         }
     }
 
+    /// Entrance of the dungeon a player is in if its map went missing
+    fn missing_dungeon_entrance(&self, player_id: &PlayerId) -> Option<(i32, i32)> {
+        let player = self.players.get(player_id)?;
+        let entrance = player.dungeon_entrance_pos?;
+        if player.current_map_type != MapType::Dungeon || self.dungeons.contains_key(&entrance) {
+            return None;
+        }
+        Some(entrance)
+    }
+
+    /// Put back a player's missing dungeon and resend it, so they aren't left walking
+    /// into walls of a map that no longer exists. Dungeons are generated from their
+    /// entrance, so the player's spot is unchanged.
+    fn restore_dungeon(&mut self, player_id: &PlayerId, entrance: (i32, i32), dungeon_map: GameMap) {
+        log::warn!("Dungeon at ({}, {}) was missing for player {}, regenerated it", entrance.0, entrance.1, player_id);
+        self.send_to_player(player_id, ServerMessage::DungeonData {
+            dungeon_map: GameLogic::game_map_to_network(&dungeon_map),
+        });
+        self.dungeons.insert(entrance, dungeon_map);
+    }
+
+    /// Rebuild a missing dungeon in place, for callers that didn't regenerate it
+    /// ahead of time with `pregenerate_dungeon` (replays)
+    fn restore_missing_dungeon(&mut self, player_id: &PlayerId) {
+        if let Some(entrance) = self.missing_dungeon_entrance(player_id) {
+            let dungeon_map = GameLogic::generate_dungeon_map_for_entrance(entrance.0, entrance.1);
+            self.restore_dungeon(player_id, entrance, dungeon_map);
+        }
+    }
+
+    /// Entrance the player is standing on if its dungeon still has to be generated
+    fn pending_dungeon_entrance(&mut self, player_id: &PlayerId) -> Option<(i32, i32)> {
+        let player = self.players.get(player_id)?;
+        if player.current_map_type != MapType::Overworld {
+            return None;
+        }
+
+        let entrance = (player.x, player.y);
+        if self.dungeons.contains_key(&entrance)
+            || !GameLogic::is_at_chunk_dungeon_entrance(&mut self.chunk_manager, entrance.0, entrance.1)
+        {
+            return None;
+        }
+        Some(entrance)
+    }
+
     fn enter_dungeon(&mut self, player_id: &PlayerId) -> Result<(), String> {
         // First check if player exists and get their current state
         let (player_x, player_y, player_name, is_in_overworld) = {
@@ -220,17 +452,19 @@ impl ServerGameState {
             player.y = spawn_y;
             player.current_map_type = MapType::Dungeon;
 
-            // Send the dungeon map to the player
-            let network_dungeon_map = GameLogic::game_map_to_network(&dungeon_map);
-            self.send_to_player(player_id, ServerMessage::DungeonData { 
-                dungeon_map: network_dungeon_map 
+            self.events.push(GameEvent::EnteredDungeon {
+                player_id: player_id.clone(),
+                dungeon_map: GameLogic::game_map_to_network(&dungeon_map),
+            });
+            self.events.push(GameEvent::StateChanged);
+            self.events.push(GameEvent::Notice {
+                audience: Audience::Everyone,
+                text: format!(
+                    "{} descends into a {} dungeon...",
+                    player_name,
+                    GameLogic::dungeon_difficulty_name(GameLogic::dungeon_difficulty(player_x, player_y))
+                ),
             });
-
-            self.broadcast_game_state();
-            let msg = ServerMessage::Message {
-                text: format!("{} descends into the dungeon...", player_name),
-            };
-            self.broadcast_to_all(msg);
             Ok(())
         } else {
             Err("Player not found.".to_string())
@@ -239,9 +473,9 @@ impl ServerGameState {
 
     fn exit_dungeon(&mut self, player_id: &PlayerId) -> Result<(), String> {
         // First check if player exists and get their current state
-        let (player_name, is_in_dungeon, player_x, player_y) = {
+        let (is_in_dungeon, player_x, player_y) = {
             if let Some(player) = self.players.get(player_id) {
-                (player.name.clone(), player.current_map_type == MapType::Dungeon, player.x, player.y)
+                (player.current_map_type == MapType::Dungeon, player.x, player.y)
             } else {
                 return Err("Player not found.".to_string());
             }
@@ -270,28 +504,135 @@ impl ServerGameState {
             }
         }
 
-        // Now move the player to the overworld
+        self.leave_dungeon(player_id, "emerges from the dungeon into the overworld.")
+    }
+
+    /// Let a turn pass for the player without them moving
+    fn wait_turn(&mut self, player_id: &PlayerId) -> Result<(), String> {
+        if !self.players.contains_key(player_id) {
+            return Err("Player not found.".to_string());
+        }
+        self.turn_count += 1;
+        self.events.push(GameEvent::StateChanged);
+        Ok(())
+    }
+
+    /// Read one of the player's items; only recall scrolls do anything in multiplayer
+    fn use_item(&mut self, player_id: &PlayerId, item: Item) -> Result<(), String> {
+        let player = self.players.get_mut(player_id).ok_or("Player not found.")?;
+        match item {
+            Item::RecallScroll => {
+                GameLogic::read_recall_scroll(&mut player.inventory, player.current_map_type)?;
+                self.leave_dungeon(player_id, "reads a recall scroll and is pulled back to the surface.")
+            }
+            Item::Food => Err("There's no need to eat here.".to_string()),
+            // Clients look through it themselves; dungeons don't depend on the server
+            Item::Spyglass => Err("There is nothing for the server to do with a spyglass.".to_string()),
+        }
+    }
+
+    /// Move a player from their dungeon back to its entrance, telling everyone `how`
+    fn leave_dungeon(&mut self, player_id: &PlayerId, how: &str) -> Result<(), String> {
+        // Use stored entrance position or fall back to default spawn, next to it if someone is there
+        let target = self.players.get(player_id)
+            .and_then(|player| player.dungeon_entrance_pos)
+            .unwrap_or(self.spawn);
+        let occupied = self.occupied_overworld_tiles(player_id);
+        let (spawn_x, spawn_y) = GameLogic::find_free_overworld_spawn(&mut self.chunk_manager, target, &occupied);
+
         if let Some(player) = self.players.get_mut(player_id) {
-            // Use stored entrance position or fall back to default spawn
-            let (spawn_x, spawn_y) = player.dungeon_entrance_pos
-                .unwrap_or_else(|| GameLogic::get_overworld_spawn_position());
-            
+            let player_name = player.name.clone();
             player.x = spawn_x;
             player.y = spawn_y;
             player.current_map_type = MapType::Overworld;
             player.dungeon_entrance_pos = None; // Clear the stored entrance position
 
-            self.broadcast_game_state();
-            let msg = ServerMessage::Message {
-                text: format!("{} emerges from the dungeon into the overworld.", player_name),
-            };
-            self.broadcast_to_all(msg);
+            self.events.push(GameEvent::StateChanged);
+            self.events.push(GameEvent::Notice {
+                audience: Audience::Everyone,
+                text: format!("{} {}", player_name, how),
+            });
             Ok(())
         } else {
             Err("Player not found.".to_string())
         }
     }
 
+    fn fast_travel(&mut self, player_id: &PlayerId, x: i32, y: i32) -> Result<(), String> {
+        let is_in_overworld = match self.players.get(player_id) {
+            Some(player) => player.current_map_type == MapType::Overworld,
+            None => return Err("Player not found.".to_string()),
+        };
+
+        if !is_in_overworld {
+            return Err("You can only fast travel from the overworld.".to_string());
+        }
+
+        let has_visited = self.visited_villages
+            .get(player_id)
+            .is_some_and(|villages| villages.contains(&(x, y)));
+        if !has_visited {
+            return Err("You can only travel to villages you have visited.".to_string());
+        }
+
+        // Load the destination area before moving there
+        self.chunk_manager.update_player_position(x, y);
+        if self.chunk_manager.get_tile(x, y) != Some(Tile::Village) {
+            return Err("That village is no longer there.".to_string());
+        }
+        let occupied = self.occupied_overworld_tiles(player_id);
+        let (x, y) = GameLogic::find_free_overworld_spawn(&mut self.chunk_manager, (x, y), &occupied);
+
+        if let Some(player) = self.players.get_mut(player_id) {
+            player.x = x;
+            player.y = y;
+        }
+        self.turn_count += 1;
+
+        self.events.push(GameEvent::Notice {
+            audience: Audience::Player(player_id.clone()),
+            text: GameLogic::get_fast_travel_message(x, y),
+        });
+        self.events.push(GameEvent::PlayerMoved {
+            player_id: player_id.clone(),
+            x,
+            y,
+        });
+        self.events.push(GameEvent::StateChanged);
+        Ok(())
+    }
+
+    fn modify_tile(&mut self, player_id: &PlayerId, x: i32, y: i32, modification: TileModification) -> Result<(), String> {
+        let (player_x, player_y, is_in_overworld) = {
+            if let Some(player) = self.players.get(player_id) {
+                (player.x, player.y, player.current_map_type == MapType::Overworld)
+            } else {
+                return Err("Player not found.".to_string());
+            }
+        };
+
+        if !is_in_overworld {
+            return Err("You can't reshape the dungeon.".to_string());
+        }
+
+        // Players can only work the tile they stand on or one next to it
+        if (x - player_x).abs() > 1 || (y - player_y).abs() > 1 {
+            return Err("That tile is out of reach.".to_string());
+        }
+
+        let tile = self.chunk_manager.get_tile(x, y).ok_or("There is nothing there to work on.")?;
+        let new_tile = GameLogic::apply_tile_modification(tile, modification)?;
+        self.chunk_manager.set_tile(x, y, new_tile);
+        self.turn_count += 1;
+
+        self.events.push(GameEvent::Notice {
+            audience: Audience::Player(player_id.clone()),
+            text: GameLogic::get_tile_modification_message(modification),
+        });
+        self.events.push(GameEvent::TileChanged { x, y, tile: new_tile });
+        Ok(())
+    }
+
     fn update_player_screen(&mut self, player_id: &PlayerId, screen: NetworkCurrentScreen) {
         if let Some(player) = self.players.get_mut(player_id) {
             player.current_screen = screen;
@@ -300,7 +641,16 @@ impl ServerGameState {
     }
 
     fn handle_chat_message(&mut self, player_id: &PlayerId, message: String) {
+        // Clients filter their input, but a modified one could send anything
+        let message = GameLogic::sanitize_chat(&message);
+        if message.trim().is_empty() {
+            return;
+        }
         if let Some(player) = self.players.get(player_id) {
+            self.chat_history.push_back((player.name.clone(), message.clone()));
+            if self.chat_history.len() > GameConstants::SERVER_CHAT_HISTORY {
+                self.chat_history.pop_front();
+            }
             let chat_msg = ServerMessage::ChatMessage {
                 player_name: player.name.clone(),
                 message,
@@ -309,23 +659,56 @@ impl ServerGameState {
         }
     }
 
+    /// Send the queued events to the clients they concern, in the order they happened
+    fn flush_events(&mut self) {
+        for event in std::mem::take(&mut self.events) {
+            let (audience, message) = event.into_message(|| GameState {
+                players: self.players.clone(),
+                turn_count: self.turn_count,
+            });
+            match audience {
+                Audience::Player(player_id) => self.send_to_player(&player_id, message),
+                Audience::Everyone => self.broadcast_to_all(message),
+            }
+        }
+    }
+
     fn broadcast_to_all(&self, message: ServerMessage) {
-        for sender in self.client_senders.values() {
-            let _ = sender.send(message.clone());
+        for (player_id, sender) in &self.client_senders {
+            let _ = sender.send(Self::private_to(player_id, message.clone()));
         }
     }
 
     fn broadcast_to_others(&self, exclude_player_id: &PlayerId, message: ServerMessage) {
         for (player_id, sender) in &self.client_senders {
             if player_id != exclude_player_id {
-                let _ = sender.send(message.clone());
+                let _ = sender.send(Self::private_to(player_id, message.clone()));
             }
         }
     }
 
     fn send_to_player(&self, player_id: &PlayerId, message: ServerMessage) {
         if let Some(sender) = self.client_senders.get(player_id) {
-            let _ = sender.send(message);
+            let _ = sender.send(Self::private_to(player_id, message));
+        }
+    }
+
+    /// Keep everyone else's inventory out of a game state sent to a player
+    fn private_to(player_id: &PlayerId, message: ServerMessage) -> ServerMessage {
+        match message {
+            ServerMessage::GameState { mut state } => {
+                for (id, player) in state.players.iter_mut() {
+                    if id != player_id {
+                        player.inventory.clear();
+                    }
+                }
+                ServerMessage::GameState { state }
+            }
+            ServerMessage::PlayerJoined { player_id: id, mut player } if &id != player_id => {
+                player.inventory.clear();
+                ServerMessage::PlayerJoined { player_id: id, player }
+            }
+            message => message,
         }
     }
 
@@ -353,17 +736,13 @@ impl ServerGameState {
             );
             
             // Convert world coordinates to local chunk coordinates
-            let mut chunk_tiles = std::collections::HashMap::new();
-            for ((world_x, world_y), tile) in tiles_in_chunk {
-                let local_x = world_x - chunk_start_x;
-                let local_y = world_y - chunk_start_y;
-                chunk_tiles.insert(format!("{},{}", local_x, local_y), tile);
-            }
+            let local_tiles = tiles_in_chunk.into_iter()
+                .map(|((world_x, world_y), tile)| ((world_x - chunk_start_x, world_y - chunk_start_y), tile));
             
             chunk_data.push(ChunkData {
                 chunk_x,
                 chunk_y,
-                tiles: chunk_tiles,
+                tiles: encode_tiles(local_tiles),
             });
         }
         
@@ -371,7 +750,123 @@ impl ServerGameState {
         self.send_to_player(player_id, ServerMessage::ChunkData { chunks: chunk_data });
     }
 
+    /// Handle a message from a client and send out the events it caused.
+    /// Returns false once the client has disconnected.
+    fn handle_client_message(&mut self, player_id: &PlayerId, client_sender: &ClientSender, client_msg: ClientMessage) -> bool {
+        let connected = self.dispatch(player_id, client_sender, client_msg);
+        self.flush_events();
+        connected
+    }
+
+    fn dispatch(&mut self, player_id: &PlayerId, client_sender: &ClientSender, client_msg: ClientMessage) -> bool {
+        self.messages_since_stats += 1;
+        if let Some(ref recorder) = self.recorder {
+            if let Ok(mut recorder) = recorder.lock() {
+                recorder.record(&self.room, player_id, &client_msg);
+            }
+        }
+
+        match client_msg {
+            ClientMessage::Connect { player_name, .. } => {
+                self.add_player(player_id.clone(), player_name, client_sender.clone());
+                
+                // Send connection confirmation
+                let _ = client_sender.send(ServerMessage::Connected {
+                    player_id: player_id.clone(),
+                });
+                if !self.chat_history.is_empty() {
+                    let _ = client_sender.send(ServerMessage::ChatHistory {
+                        messages: self.chat_history.iter().cloned().collect(),
+                    });
+                }
+                
+                // Send initial game state
+                self.broadcast_game_state();
+            }
+            ClientMessage::Move { dx, dy } => {
+                match self.move_player(player_id, dx, dy) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        // Send blocked movement message as regular message to match single-player experience
+                        self.send_to_player(player_id, ServerMessage::Message {
+                            text: err,
+                        });
+                    }
+                }
+            }
+            ClientMessage::RequestChunks { chunks } => {
+                self.handle_chunk_request(player_id, chunks);
+            }
+            ClientMessage::RequestDungeonData => {
+                self.handle_dungeon_data_request(player_id);
+            }
+            ClientMessage::EnterDungeon => {
+                match self.enter_dungeon(player_id) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        self.send_to_player(player_id, ServerMessage::Error {
+                            message: err,
+                        });
+                    }
+                }
+            }
+            ClientMessage::ExitDungeon => {
+                match self.exit_dungeon(player_id) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        self.send_to_player(player_id, ServerMessage::Error {
+                            message: err,
+                        });
+                    }
+                }
+            }
+            ClientMessage::ModifyTile { x, y, modification } => {
+                if let Err(err) = self.modify_tile(player_id, x, y, modification) {
+                    self.send_to_player(player_id, ServerMessage::Message {
+                        text: err,
+                    });
+                }
+            }
+            ClientMessage::FastTravel { x, y } => {
+                if let Err(err) = self.fast_travel(player_id, x, y) {
+                    self.send_to_player(player_id, ServerMessage::Message {
+                        text: err,
+                    });
+                }
+            }
+            ClientMessage::OpenInventory => {
+                self.update_player_screen(player_id, NetworkCurrentScreen::Inventory);
+            }
+            ClientMessage::CloseInventory => {
+                self.update_player_screen(player_id, NetworkCurrentScreen::Game);
+            }
+            ClientMessage::Chat { message } => {
+                self.handle_chat_message(player_id, message);
+            }
+            ClientMessage::UseItem { item } => {
+                if let Err(err) = self.use_item(player_id, item) {
+                    self.send_to_player(player_id, ServerMessage::Error {
+                        message: err,
+                    });
+                }
+            }
+            ClientMessage::Wait => {
+                if let Err(err) = self.wait_turn(player_id) {
+                    self.send_to_player(player_id, ServerMessage::Error {
+                        message: err,
+                    });
+                }
+            }
+            ClientMessage::Disconnect => {
+                self.remove_player(player_id);
+                return false;
+            }
+        }
+        true
+    }
+
     fn handle_dungeon_data_request(&mut self, player_id: &PlayerId) {
+        self.restore_missing_dungeon(player_id);
         if let Some(player) = self.players.get(player_id) {
             if player.current_map_type == MapType::Dungeon {
                 if let Some((entrance_x, entrance_y)) = player.dungeon_entrance_pos {
@@ -390,23 +885,223 @@ impl ServerGameState {
 
 #[tokio::main]
 async fn main() {
-    println!("Starting roguelike server on 127.0.0.1:8080");
+    // Server status goes to stdout at info level unless RUST_LOG says otherwise
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .target(env_logger::Target::Stdout)
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let flag_value = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1));
+
+    let mut rooms = Rooms::default();
+    if let Some(value) = flag_value("--max-players") {
+        match value.parse::<usize>() {
+            Ok(max_players) if max_players > 0 => {
+                rooms.max_players = Some(max_players);
+                log::info!("Allowing at most {} players", max_players);
+            }
+            _ => {
+                log::error!("--max-players must be a positive number, got '{}'", value);
+                return;
+            }
+        }
+    }
+
+    if let Some(value) = flag_value("--spawn") {
+        let spawn = value.split_once(',')
+            .and_then(|(x, y)| Some((x.trim().parse::<i32>().ok()?, y.trim().parse::<i32>().ok()?)));
+        match spawn {
+            Some(spawn) => {
+                rooms.spawn = Some(spawn);
+                log::info!("Spawning new players near {},{}", spawn.0, spawn.1);
+            }
+            None => {
+                log::error!("--spawn needs a position like 30,15, got '{}'", value);
+                return;
+            }
+        }
+    }
+
+    if let Some(value) = flag_value("--difficulty") {
+        match Difficulty::parse(value) {
+            Some(difficulty) => {
+                rooms.difficulty = difficulty;
+                log::info!("Difficulty: {}", difficulty.name());
+            }
+            None => {
+                log::error!("--difficulty needs easy, normal or hard, got '{}'", value);
+                return;
+            }
+        }
+    }
+
+    if let Some(path) = flag_value("--biomes") {
+        let biomes = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path, err))
+            .and_then(|json| serde_json::from_str::<BiomeConfig>(&json)
+                .map_err(|err| format!("Failed to parse {}: {}", path, err)))
+            .and_then(|biomes| biomes.validate().map(|_| biomes));
+        match biomes {
+            Ok(biomes) => {
+                rooms.biomes = biomes;
+                log::info!("Using biome thresholds from {}", path);
+            }
+            Err(err) => {
+                log::error!("--biomes: {}", err);
+                return;
+            }
+        }
+    }
+
+    // Report the terrain mix a seed and biome config give, then exit
+    if let Some(value) = flag_value("--world-stats") {
+        match value.parse::<u32>() {
+            Ok(seed) => {
+                let generator = InfiniteTerrainGenerator::with_biomes(seed, rooms.biomes);
+                let radius = GameConstants::WORLD_STATS_RADIUS;
+                println!("World {} within {} tiles of the origin:", seed, radius);
+                println!("{}", generator.sample_biomes(0, 0, radius));
+            }
+            Err(_) => log::error!("--world-stats needs a world seed, got '{}'", value),
+        }
+        return;
+    }
+
+    // Replays get the same world options, but never record or touch saved worlds
+    if let Some(path) = flag_value("--replay") {
+        if let Err(err) = recording::replay(path, &rooms) {
+            log::error!("{}", err);
+        }
+        return;
+    }
+
+    rooms.persistent = true;
+    if let Some(path) = flag_value("--record") {
+        let recorder = Recorder::create(path).expect("Failed to start recording");
+        rooms.recorder = Some(Arc::new(std::sync::Mutex::new(recorder)));
+        log::info!("Recording client messages to {}", path);
+    }
+
+    // LAN games have to be reachable from other machines
+    let lan = args.iter().any(|arg| arg == "--lan");
+    let address = if lan { "0.0.0.0:8080" } else { "127.0.0.1:8080" };
+    log::info!("Starting roguelike server on {}", address);
     
-    let listener = TcpListener::bind("127.0.0.1:8080").await.expect("Failed to bind");
-    let game_state = Arc::new(Mutex::new(ServerGameState::new()));
+    let listener = TcpListener::bind(address).await.expect("Failed to bind");
+    let rooms = Arc::new(Mutex::new(rooms));
+    tokio::spawn(log_stats_periodically(Arc::clone(&rooms)));
+    if lan {
+        let port = listener.local_addr().expect("Listener has an address").port();
+        tokio::spawn(announce_on_lan(Arc::clone(&rooms), port));
+    }
+    tokio::select! {
+        _ = serve(listener, Arc::clone(&rooms)) => {}
+        _ = shutdown_signal() => log::info!("Shutting down"),
+    }
+    // Rooms only save on eviction or when they empty, so flush what's still in memory
+    rooms.lock().await.save_all().await;
+}
+
+/// Resolves on Ctrl-C, or when the process is asked to terminate
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
 
+/// Accept WebSocket clients on the listener until it fails
+async fn serve(listener: TcpListener, rooms: SharedRooms) {
     while let Ok((stream, addr)) = listener.accept().await {
-        println!("New connection from: {}", addr);
-        let game_state = Arc::clone(&game_state);
-        tokio::spawn(handle_client(stream, game_state));
+        log::info!("New connection from: {}", addr);
+        let rooms = Arc::clone(&rooms);
+        tokio::spawn(handle_client(stream, rooms));
     }
 }
 
-async fn handle_client(stream: TcpStream, game_state: SharedGameState) {
+/// Give operators a regular view of server load
+async fn log_stats_periodically(rooms: SharedRooms) {
+    let interval = Duration::from_secs(GameConstants::SERVER_STATS_INTERVAL_SECS);
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await; // The first tick fires immediately
+    loop {
+        ticker.tick().await;
+        rooms.lock().await.log_stats(interval).await;
+    }
+}
+
+/// Broadcast the server's port and player count so clients' LAN lists can find it
+async fn announce_on_lan(rooms: SharedRooms, port: u16) {
+    let socket = match tokio::net::UdpSocket::bind("0.0.0.0:0").await {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::error!("Failed to open the LAN announcement socket: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = socket.set_broadcast(true) {
+        log::error!("Failed to enable LAN broadcasts: {}", e);
+        return;
+    }
+    log::info!("Announcing on the LAN (UDP port {})", GameConstants::LAN_DISCOVERY_PORT);
+
+    let mut ticker = tokio::time::interval(Duration::from_secs(GameConstants::LAN_ANNOUNCE_INTERVAL_SECS));
+    loop {
+        ticker.tick().await;
+        let announcement = LanAnnouncement { port, players: rooms.lock().await.players_connected };
+        let json = serde_json::to_vec(&announcement).unwrap();
+        if let Err(e) = socket.send_to(&json, ("255.255.255.255", GameConstants::LAN_DISCOVERY_PORT)).await {
+            log::warn!("Failed to send LAN announcement: {}", e);
+        }
+    }
+}
+
+/// Build a dungeon on the blocking thread pool instead of inside the game state lock
+fn start_dungeon_generation((entrance_x, entrance_y): (i32, i32)) -> tokio::task::JoinHandle<GameMap> {
+    tokio::task::spawn_blocking(move || {
+        log::debug!("Generating dungeon for entrance ({}, {})", entrance_x, entrance_y);
+        GameLogic::generate_dungeon_map_for_entrance(entrance_x, entrance_y)
+    })
+}
+
+/// Generate the dungeon a client message needs without holding the lock, so other
+/// players in the room aren't stalled while it's built: the one a player is about
+/// to enter, or the one they are moving in if it went missing
+async fn pregenerate_dungeon(game_state: &SharedGameState, player_id: &PlayerId, client_msg: &ClientMessage) {
+    let entrance = {
+        let mut state = game_state.lock().await;
+        match client_msg {
+            ClientMessage::EnterDungeon => state.pending_dungeon_entrance(player_id),
+            ClientMessage::Move { .. } => state.missing_dungeon_entrance(player_id),
+            _ => None,
+        }
+    };
+    let Some(entrance) = entrance else {
+        return;
+    };
+    if let Ok(dungeon) = start_dungeon_generation(entrance).await {
+        let mut state = game_state.lock().await;
+        if matches!(client_msg, ClientMessage::EnterDungeon) {
+            // Someone else may have entered meanwhile; keep the dungeon they got
+            state.dungeons.entry(entrance).or_insert(dungeon);
+        } else if state.missing_dungeon_entrance(player_id) == Some(entrance) {
+            state.restore_dungeon(player_id, entrance, dungeon);
+        }
+    }
+}
+
+async fn handle_client(stream: TcpStream, rooms: SharedRooms) {
     let ws_stream = match accept_async(stream).await {
         Ok(ws) => ws,
         Err(e) => {
-            println!("WebSocket connection error: {}", e);
+            log::error!("WebSocket connection error: {}", e);
             return;
         }
     };
@@ -423,88 +1118,561 @@ async fn handle_client(stream: TcpStream, game_state: SharedGameState) {
                 break;
             }
         }
+        // Every sender is gone, so close the socket with a proper close frame
+        let _ = ws_sender.close().await;
     });
 
+    // The room is picked by the client's Connect message
+    let mut joined: Option<(String, SharedGameState)> = None;
+
     // Handle incoming messages from client
     while let Some(msg) = ws_receiver.next().await {
         match msg {
             Ok(Message::Text(text)) => {
                 if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
-                    let mut state = game_state.lock().await;
-                    
-                    match client_msg {
-                        ClientMessage::Connect { player_name } => {
-                            state.add_player(player_id.clone(), player_name, client_sender.clone());
-                            
-                            // Send connection confirmation
-                            let _ = client_sender.send(ServerMessage::Connected {
-                                player_id: player_id.clone(),
-                            });
-                            
-                            // Send initial game state
-                            state.broadcast_game_state();
-                        }
-                        ClientMessage::Move { dx, dy } => {
-                            match state.move_player(&player_id, dx, dy) {
-                                Ok(_) => {}
-                                Err(err) => {
-                                    // Send blocked movement message as regular message to match single-player experience
-                                    state.send_to_player(&player_id, ServerMessage::Message {
-                                        text: err,
-                                    });
-                                }
+                    if joined.is_none() {
+                        let ClientMessage::Connect { ref room, .. } = client_msg else {
+                            continue; // Nothing to do until the client joins a room
+                        };
+                        let room = match GameLogic::normalize_room_code(room.as_deref()) {
+                            Ok(room) => room,
+                            Err(message) => {
+                                let _ = client_sender.send(ServerMessage::Error { message });
+                                continue;
                             }
-                        }
-                        ClientMessage::RequestChunks { chunks } => {
-                            state.handle_chunk_request(&player_id, chunks);
-                        }
-                        ClientMessage::RequestDungeonData => {
-                            state.handle_dungeon_data_request(&player_id);
-                        }
-                        ClientMessage::EnterDungeon => {
-                            match state.enter_dungeon(&player_id) {
-                                Ok(_) => {}
-                                Err(err) => {
-                                    state.send_to_player(&player_id, ServerMessage::Error {
-                                        message: err,
-                                    });
-                                }
-                            }
-                        }
-                        ClientMessage::ExitDungeon => {
-                            match state.exit_dungeon(&player_id) {
-                                Ok(_) => {}
-                                Err(err) => {
-                                    state.send_to_player(&player_id, ServerMessage::Error {
-                                        message: err,
-                                    });
-                                }
+                        };
+                        match rooms.lock().await.join(&room) {
+                            Ok(game_state) => joined = Some((room, game_state)),
+                            Err(message) => {
+                                // Full servers turn the client away and hang up
+                                let _ = client_sender.send(ServerMessage::Error { message });
+                                break;
                             }
                         }
-                        ClientMessage::OpenInventory => {
-                            state.update_player_screen(&player_id, NetworkCurrentScreen::Inventory);
-                        }
-                        ClientMessage::CloseInventory => {
-                            state.update_player_screen(&player_id, NetworkCurrentScreen::Game);
-                        }
-                        ClientMessage::Chat { message } => {
-                            state.handle_chat_message(&player_id, message);
-                        }
-                        ClientMessage::Disconnect => {
-                            state.remove_player(&player_id);
+                    }
+
+                    if let Some((_, ref game_state)) = joined {
+                        pregenerate_dungeon(game_state, &player_id, &client_msg).await;
+
+                        let mut state = game_state.lock().await;
+                        if !state.handle_client_message(&player_id, &client_sender, client_msg) {
                             break;
                         }
                     }
                 }
             }
             Ok(Message::Close(_)) | Err(_) => {
-                let mut state = game_state.lock().await;
-                state.remove_player(&player_id);
+                // Treated as a disconnect so recordings see it too
+                if let Some((_, ref game_state)) = joined {
+                    let mut state = game_state.lock().await;
+                    state.handle_client_message(&player_id, &client_sender, ClientMessage::Disconnect);
+                }
                 break;
             }
             _ => {}
         }
     }
 
-    println!("Client disconnected: {}", player_id);
+    if let Some((room, _)) = joined {
+        rooms.lock().await.leave(&room).await;
+    }
+
+    log::info!("Client disconnected: {}", player_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_dungeon_entrance(state: &mut ServerGameState) -> (i32, i32) {
+        for y in -300..300 {
+            for x in -300..300 {
+                if state.chunk_manager.get_tile(x, y) == Some(Tile::DungeonEntrance) {
+                    return (x, y);
+                }
+            }
+        }
+        panic!("no dungeon entrance near the origin");
+    }
+
+    #[test]
+    fn player_colors_stay_distinct_and_avoid_yellow() {
+        let colors: Vec<(u8, u8, u8)> = (0..64).map(player_color).collect();
+        let unique: HashSet<_> = colors.iter().collect();
+        assert_eq!(unique.len(), colors.len());
+
+        for &(r, g, b) in &colors {
+            // Yellow is strong red and green with little blue
+            let yellowish = r > 200 && g > 200 && b < 120;
+            assert!(!yellowish, "({}, {}, {}) is too close to the local player's yellow", r, g, b);
+        }
+    }
+
+    #[tokio::test]
+    async fn players_can_move_while_a_dungeon_generates() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (explorer_sender, _explorer_receiver) = mpsc::unbounded_channel();
+        let (walker_sender, _walker_receiver) = mpsc::unbounded_channel();
+        let explorer = "explorer".to_string();
+        let walker = "walker".to_string();
+        state.add_player(explorer.clone(), "Explorer".to_string(), explorer_sender);
+        state.add_player(walker.clone(), "Walker".to_string(), walker_sender);
+
+        let (entrance_x, entrance_y) = find_dungeon_entrance(&mut state);
+        let player = state.players.get_mut(&explorer).unwrap();
+        player.x = entrance_x;
+        player.y = entrance_y;
+
+        let game_state: SharedGameState = Arc::new(Mutex::new(state));
+        let generation = tokio::spawn({
+            let game_state = Arc::clone(&game_state);
+            let explorer = explorer.clone();
+            async move { pregenerate_dungeon(&game_state, &explorer, &ClientMessage::EnterDungeon).await }
+        });
+        // On this single threaded runtime the task runs until it waits for the
+        // dungeon, and can't finish until the test yields again
+        tokio::task::yield_now().await;
+
+        // The lock is free while the dungeon is built, so the other player keeps moving
+        {
+            let mut state = game_state.try_lock().expect("game state locked during generation");
+            assert!(!generation.is_finished());
+            let moved = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .iter()
+                .any(|&(dx, dy)| state.move_player(&walker, dx, dy).is_ok());
+            assert!(moved, "walker should be able to move");
+        }
+
+        generation.await.unwrap();
+        let mut state = game_state.lock().await;
+        assert!(state.pending_dungeon_entrance(&explorer).is_none());
+
+        state.enter_dungeon(&explorer).unwrap();
+        assert_eq!(state.players[&explorer].current_map_type, MapType::Dungeon);
+    }
+
+    #[tokio::test]
+    async fn a_room_stays_open_for_a_player_still_joining() {
+        let mut rooms = Rooms::default();
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let host = rooms.join("cave").unwrap();
+        host.lock().await.add_player("host".to_string(), "Host".to_string(), sender);
+
+        // The guest has the room but hasn't been added to it when the host leaves
+        let guest = rooms.join("cave").unwrap();
+        host.lock().await.remove_player(&"host".to_string());
+        rooms.leave("cave").await;
+        assert!(Arc::ptr_eq(&guest, &rooms.worlds["cave"]), "the guest's room was closed under them");
+
+        rooms.leave("cave").await;
+        assert!(!rooms.worlds.contains_key("cave"));
+        assert_eq!(rooms.players_connected, 0);
+    }
+
+    #[test]
+    fn new_players_spawn_on_distinct_walkable_tiles() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        for i in 0..8 {
+            let (sender, _receiver) = mpsc::unbounded_channel();
+            state.add_player(format!("player{}", i), format!("Player {}", i), sender);
+        }
+
+        let positions: HashSet<(i32, i32)> = state.players.values().map(|player| (player.x, player.y)).collect();
+        assert_eq!(positions.len(), state.players.len(), "players spawned on the same tile");
+        for &(x, y) in &positions {
+            let tile = state.chunk_manager.get_tile(x, y);
+            assert!(tile.is_some_and(GameLogic::is_movement_valid), "spawned on {:?} at ({}, {})", tile, x, y);
+        }
+    }
+
+    #[test]
+    fn recall_scroll_returns_the_reader_to_the_entrance() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let reader = "reader".to_string();
+        state.add_player(reader.clone(), "Reader".to_string(), sender);
+
+        let entrance = find_dungeon_entrance(&mut state);
+        let player = state.players.get_mut(&reader).unwrap();
+        (player.x, player.y) = entrance;
+        state.enter_dungeon(&reader).unwrap();
+
+        // Nobody can read a scroll they don't have
+        assert!(state.use_item(&reader, Item::RecallScroll).is_err());
+        assert_eq!(state.players[&reader].current_map_type, MapType::Dungeon);
+
+        state.players.get_mut(&reader).unwrap().inventory.push(Item::RecallScroll);
+        state.use_item(&reader, Item::RecallScroll).unwrap();
+        let player = &state.players[&reader];
+        assert_eq!(player.current_map_type, MapType::Overworld);
+        assert_eq!((player.x, player.y), entrance);
+        assert!(player.inventory.is_empty());
+    }
+
+    #[test]
+    fn inventories_stay_private_and_failed_item_use_is_an_error() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (reader_sender, mut reader_receiver) = mpsc::unbounded_channel();
+        let (watcher_sender, mut watcher_receiver) = mpsc::unbounded_channel();
+        let reader = "reader".to_string();
+        let watcher = "watcher".to_string();
+        state.add_player(reader.clone(), "Reader".to_string(), reader_sender.clone());
+        state.add_player(watcher.clone(), "Watcher".to_string(), watcher_sender);
+        state.players.get_mut(&reader).unwrap().inventory = vec![Item::RecallScroll, Item::Food];
+
+        state.handle_client_message(&reader, &reader_sender, ClientMessage::UseItem { item: Item::Food });
+        let reader_sees: Vec<_> = std::iter::from_fn(|| reader_receiver.try_recv().ok()).collect();
+        assert!(reader_sees.iter().any(|message| matches!(message, ServerMessage::Error { .. })));
+
+        state.broadcast_game_state();
+        let inventory_of_reader = |receiver: &mut ClientReceiver| {
+            std::iter::from_fn(|| receiver.try_recv().ok())
+                .find_map(|message| match message {
+                    ServerMessage::GameState { state } => Some(state.players[&reader].inventory.clone()),
+                    _ => None,
+                })
+                .expect("a game state")
+        };
+        assert_eq!(inventory_of_reader(&mut reader_receiver), vec![Item::RecallScroll, Item::Food]);
+        assert!(inventory_of_reader(&mut watcher_receiver).is_empty());
+    }
+
+    #[test]
+    fn leaving_a_dungeon_returns_to_the_exact_entrance() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let explorer = "explorer".to_string();
+        state.add_player(explorer.clone(), "Explorer".to_string(), sender);
+
+        let entrance = find_dungeon_entrance(&mut state);
+        let player = state.players.get_mut(&explorer).unwrap();
+        (player.x, player.y) = entrance;
+        state.enter_dungeon(&explorer).unwrap();
+        assert_eq!(state.players[&explorer].dungeon_entrance_pos, Some(entrance));
+
+        // Wander off the exit, check it can't be used from there, then walk back
+        let exit = (state.players[&explorer].x, state.players[&explorer].y);
+        let step = [(1, 0), (-1, 0), (0, 1), (0, -1)].into_iter()
+            .find(|&(dx, dy)| state.move_player(&explorer, dx, dy).is_ok())
+            .expect("the exit has an open neighbour");
+        assert_ne!((state.players[&explorer].x, state.players[&explorer].y), exit);
+        assert!(state.exit_dungeon(&explorer).is_err());
+        state.move_player(&explorer, -step.0, -step.1).unwrap();
+
+        state.exit_dungeon(&explorer).unwrap();
+        let player = &state.players[&explorer];
+        assert_eq!((player.x, player.y), entrance);
+        assert_eq!(player.current_map_type, MapType::Overworld);
+        assert_eq!(player.dungeon_entrance_pos, None);
+    }
+
+    #[test]
+    fn moves_are_queued_as_events_until_flushed() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let walker = "walker".to_string();
+        state.add_player(walker.clone(), "Walker".to_string(), sender);
+
+        let moved = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .any(|&(dx, dy)| state.move_player(&walker, dx, dy).is_ok());
+        assert!(moved, "walker should be able to move");
+        let (x, y) = (state.players[&walker].x, state.players[&walker].y);
+
+        // The rules only record what happened; nothing reaches the client yet
+        assert!(receiver.try_recv().is_err());
+        assert!(state.events.iter().any(|event| matches!(
+            event,
+            GameEvent::PlayerMoved { player_id, x: event_x, y: event_y } if *player_id == walker && (*event_x, *event_y) == (x, y)
+        )));
+        assert!(matches!(state.events.last(), Some(GameEvent::StateChanged)));
+
+        state.flush_events();
+        assert!(state.events.is_empty());
+        let sent: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        assert!(sent.iter().any(|message| matches!(message, ServerMessage::PlayerMoved { .. })));
+        assert!(matches!(sent.last(), Some(ServerMessage::GameState { .. })));
+    }
+
+    #[test]
+    fn reshaped_tiles_reach_everyone_once_flushed() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (worker_sender, mut worker_receiver) = mpsc::unbounded_channel();
+        let (watcher_sender, mut watcher_receiver) = mpsc::unbounded_channel();
+        let worker = "worker".to_string();
+        state.add_player(worker.clone(), "Worker".to_string(), worker_sender);
+        state.add_player("watcher".to_string(), "Watcher".to_string(), watcher_sender);
+
+        let (x, y) = (state.players[&worker].x, state.players[&worker].y);
+        let (tx, ty) = [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)].into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .find(|&(tx, ty)| state.chunk_manager.get_tile(tx, ty) == Some(Tile::Grass))
+            .expect("grass next to the spawn");
+        state.modify_tile(&worker, tx, ty, TileModification::LayRoad).unwrap();
+        assert_eq!(state.chunk_manager.get_tile(tx, ty), Some(Tile::Road));
+        while worker_receiver.try_recv().is_ok() {}
+        while watcher_receiver.try_recv().is_ok() {}
+
+        state.flush_events();
+        let changed = |message: &ServerMessage| matches!(
+            message,
+            ServerMessage::TileChanged { x, y, tile: Tile::Road } if (*x, *y) == (tx, ty)
+        );
+        let worker_sees: Vec<_> = std::iter::from_fn(|| worker_receiver.try_recv().ok()).collect();
+        assert!(matches!(worker_sees.first(), Some(ServerMessage::Message { .. })));
+        assert!(worker_sees.iter().any(changed));
+        let watcher_sees: Vec<_> = std::iter::from_fn(|| watcher_receiver.try_recv().ok()).collect();
+        assert!(watcher_sees.iter().any(changed));
+        assert!(!watcher_sees.iter().any(|message| matches!(message, ServerMessage::Message { .. })));
+    }
+
+    #[test]
+    fn waiting_passes_a_turn_in_place() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let waiter = "waiter".to_string();
+        state.add_player(waiter.clone(), "Waiter".to_string(), sender.clone());
+        let (x, y) = (state.players[&waiter].x, state.players[&waiter].y);
+        let turn = state.turn_count;
+
+        state.handle_client_message(&waiter, &sender, ClientMessage::Wait);
+
+        assert_eq!(state.turn_count, turn + 1);
+        assert_eq!((state.players[&waiter].x, state.players[&waiter].y), (x, y));
+        let sent: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        assert!(matches!(sent.last(), Some(ServerMessage::GameState { state }) if state.turn_count == turn + 1));
+    }
+
+    #[test]
+    fn chat_is_sanitized_and_limited_on_the_server() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let talker = "talker".to_string();
+        state.handle_client_message(&talker, &sender, ClientMessage::Connect { player_name: "Talker".to_string(), room: None });
+
+        let pasted = format!("hi\nthere\x1b[2J{}", "x".repeat(200));
+        state.handle_client_message(&talker, &sender, ClientMessage::Chat { message: pasted });
+        // Nothing left once the control characters are gone, so nothing is sent
+        state.handle_client_message(&talker, &sender, ClientMessage::Chat { message: "\n\r\x1b".to_string() });
+
+        let chats: Vec<String> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter_map(|message| match message {
+                ServerMessage::ChatMessage { message, .. } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(chats.len(), 1);
+        assert!(chats[0].starts_with("hithere[2Jxxx"));
+        assert_eq!(chats[0].chars().count(), GameConstants::MAX_CHAT_LENGTH);
+        assert_eq!(state.chat_history.back().unwrap().1, chats[0]);
+    }
+
+    #[test]
+    fn joining_players_get_the_recent_chat() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let talker = "talker".to_string();
+        state.handle_client_message(&talker, &sender, ClientMessage::Connect { player_name: "Talker".to_string(), room: None });
+        for i in 0..GameConstants::SERVER_CHAT_HISTORY + 5 {
+            state.handle_client_message(&talker, &sender, ClientMessage::Chat { message: format!("message {}", i) });
+        }
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let newcomer = "newcomer".to_string();
+        state.handle_client_message(&newcomer, &sender, ClientMessage::Connect { player_name: "Newcomer".to_string(), room: None });
+
+        let history = std::iter::from_fn(|| receiver.try_recv().ok())
+            .find_map(|message| match message {
+                ServerMessage::ChatHistory { messages } => Some(messages),
+                _ => None,
+            })
+            .expect("the newcomer should get the chat history");
+        assert_eq!(history.len(), GameConstants::SERVER_CHAT_HISTORY);
+        assert_eq!(history.first().unwrap(), &("Talker".to_string(), "message 5".to_string()));
+        assert_eq!(history.last().unwrap().1, format!("message {}", GameConstants::SERVER_CHAT_HISTORY + 4));
+    }
+
+    #[test]
+    fn entering_a_dungeon_sends_the_servers_map() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let explorer = "explorer".to_string();
+        state.add_player(explorer.clone(), "Explorer".to_string(), sender);
+
+        let entrance = find_dungeon_entrance(&mut state);
+        let player = state.players.get_mut(&explorer).unwrap();
+        (player.x, player.y) = entrance;
+        state.enter_dungeon(&explorer).unwrap();
+        state.flush_events();
+
+        // What the client rebuilds from DungeonData is exactly the server's dungeon
+        let network_map = std::iter::from_fn(|| receiver.try_recv().ok())
+            .find_map(|message| match message {
+                ServerMessage::DungeonData { dungeon_map } => Some(dungeon_map),
+                _ => None,
+            })
+            .expect("no DungeonData sent on entry");
+        let client_map = GameLogic::network_map_to_game(&network_map).unwrap();
+        let server_map = state.dungeons[&entrance].clone();
+        assert_eq!((client_map.width, client_map.height), (server_map.width, server_map.height));
+        assert_eq!(client_map.tiles, server_map.tiles);
+
+        // Asking again, e.g. after missing the first copy, gets the same map
+        while receiver.try_recv().is_ok() {}
+        state.handle_dungeon_data_request(&explorer);
+        match receiver.try_recv() {
+            Ok(ServerMessage::DungeonData { dungeon_map }) => {
+                assert_eq!(GameLogic::network_map_to_game(&dungeon_map).unwrap().tiles, server_map.tiles);
+            }
+            other => panic!("expected DungeonData, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn moving_in_a_dropped_dungeon_regenerates_it() {
+        let mut state = ServerGameState::new(GameConstants::DEFAULT_ROOM);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let explorer = "explorer".to_string();
+        state.add_player(explorer.clone(), "Explorer".to_string(), sender);
+
+        let entrance = find_dungeon_entrance(&mut state);
+        let player = state.players.get_mut(&explorer).unwrap();
+        (player.x, player.y) = entrance;
+        state.enter_dungeon(&explorer).unwrap();
+        state.flush_events();
+        let original = state.dungeons.remove(&entrance).unwrap();
+        while receiver.try_recv().is_ok() {}
+
+        // Rebuilt off the lock before the move, and the step is checked against it
+        let (x, y) = (state.players[&explorer].x, state.players[&explorer].y);
+        let step = [(1, 0), (-1, 0), (0, 1), (0, -1)].into_iter()
+            .find(|(dx, dy)| original.tile_at(x + dx, y + dy).is_some_and(GameLogic::is_movement_valid))
+            .expect("the exit has an open neighbour");
+        let game_state: SharedGameState = Arc::new(Mutex::new(state));
+        pregenerate_dungeon(&game_state, &explorer, &ClientMessage::Move { dx: step.0, dy: step.1 }).await;
+        let mut state = game_state.lock().await;
+        assert!(state.dungeons.contains_key(&entrance));
+        state.move_player(&explorer, step.0, step.1).unwrap();
+        assert_eq!(state.dungeons[&entrance].tiles, original.tiles);
+        assert_eq!((state.players[&explorer].x, state.players[&explorer].y), (x + step.0, y + step.1));
+
+        let resent = std::iter::from_fn(|| receiver.try_recv().ok())
+            .find_map(|message| match message {
+                ServerMessage::DungeonData { dungeon_map } => Some(dungeon_map),
+                _ => None,
+            })
+            .expect("no DungeonData resent");
+        assert_eq!(GameLogic::network_map_to_game(&resent).unwrap().tiles, original.tiles);
+    }
+
+    #[tokio::test]
+    async fn websocket_protocol_round_trip() {
+        // Rooms::default() only keeps worlds in memory, so nothing is written under worlds/
+        let rooms: SharedRooms = Arc::new(Mutex::new(Rooms::default()));
+        // Created up front so the test can look at the world the server plays in
+        let world = rooms.lock().await.get_or_create("roundtrip");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, Arc::clone(&rooms)));
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+
+        // Send a client message and read server messages until one matches
+        async fn send(socket: &mut (impl SinkExt<Message> + Unpin), message: ClientMessage) {
+            let json = serde_json::to_string(&message).unwrap();
+            assert!(socket.send(Message::Text(json)).await.is_ok());
+        }
+        async fn expect<S, F>(socket: &mut S, what: &str, mut matches: F) -> ServerMessage
+        where
+            S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+            F: FnMut(&ServerMessage) -> bool,
+        {
+            let read = async {
+                while let Some(Ok(frame)) = socket.next().await {
+                    if let Message::Text(text) = frame {
+                        let message = ServerMessage::parse(&text).unwrap();
+                        if matches(&message) {
+                            return message;
+                        }
+                    }
+                }
+                panic!("connection closed while waiting for {}", what);
+            };
+            tokio::time::timeout(Duration::from_secs(10), read)
+                .await
+                .unwrap_or_else(|_| panic!("timed out waiting for {}", what))
+        }
+
+        send(&mut socket, ClientMessage::Connect {
+            player_name: "Tester".to_string(),
+            room: Some("roundtrip".to_string()),
+        }).await;
+        let player_id = match expect(&mut socket, "Connected", |m| matches!(m, ServerMessage::Connected { .. })).await {
+            ServerMessage::Connected { player_id } => player_id,
+            _ => unreachable!(),
+        };
+        let start = match expect(&mut socket, "GameState", |m| {
+            matches!(m, ServerMessage::GameState { state } if state.players.contains_key(&player_id))
+        }).await {
+            ServerMessage::GameState { state } => state.players[&player_id].clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(start.name, "Tester");
+        assert_eq!(start.current_map_type, MapType::Overworld);
+
+        // Step onto an open neighbour, which must be broadcast as the new position
+        let (dx, dy) = {
+            let mut state = world.lock().await;
+            [(1, 0), (-1, 0), (0, 1), (0, -1)].into_iter()
+                .find(|(dx, dy)| {
+                    let tile = state.chunk_manager.get_tile(start.x + dx, start.y + dy);
+                    tile.is_some_and(|tile| GameLogic::is_movement_valid(tile) && tile != Tile::DungeonEntrance)
+                })
+                .expect("the spawn has an open neighbour")
+        };
+        send(&mut socket, ClientMessage::Move { dx, dy }).await;
+        let moved = expect(&mut socket, "move result", |m| match m {
+            ServerMessage::PlayerMoved { player_id: id, .. } => id == &player_id,
+            ServerMessage::Message { .. } => true,
+            _ => false,
+        }).await;
+        assert!(
+            matches!(moved, ServerMessage::PlayerMoved { x, y, .. } if (x, y) == (start.x + dx, start.y + dy)),
+            "expected a move to ({}, {}), got {:?}", start.x + dx, start.y + dy, moved
+        );
+
+        send(&mut socket, ClientMessage::Chat { message: "hello".to_string() }).await;
+        match expect(&mut socket, "ChatMessage", |m| matches!(m, ServerMessage::ChatMessage { .. })).await {
+            ServerMessage::ChatMessage { player_name, message } => {
+                assert_eq!(player_name, "Tester");
+                assert_eq!(message, "hello");
+            }
+            _ => unreachable!(),
+        }
+
+        // Spawn is never on a dungeon entrance
+        send(&mut socket, ClientMessage::EnterDungeon).await;
+        let error = expect(&mut socket, "Error", |m| matches!(m, ServerMessage::Error { .. })).await;
+        assert!(matches!(error, ServerMessage::Error { message } if message.contains("not at a dungeon entrance")));
+
+        // From an entrance the server builds the dungeon and sends it along with the new map type
+        let entrance = {
+            let mut state = world.lock().await;
+            let entrance = find_dungeon_entrance(&mut state);
+            let player = state.players.get_mut(&player_id).unwrap();
+            (player.x, player.y) = entrance;
+            entrance
+        };
+        send(&mut socket, ClientMessage::EnterDungeon).await;
+        let dungeon_map = match expect(&mut socket, "DungeonData", |m| matches!(m, ServerMessage::DungeonData { .. })).await {
+            ServerMessage::DungeonData { dungeon_map } => dungeon_map,
+            _ => unreachable!(),
+        };
+        assert_eq!(GameLogic::network_map_to_game(&dungeon_map).unwrap().tiles, world.lock().await.dungeons[&entrance].tiles);
+        expect(&mut socket, "GameState in the dungeon", |m| {
+            matches!(m, ServerMessage::GameState { state } if state.players[&player_id].current_map_type == MapType::Dungeon)
+        }).await;
+
+        send(&mut socket, ClientMessage::Disconnect).await;
+    }
 }
@@ -0,0 +1,128 @@
+// Recording and replaying client messages, for reproducing bugs deterministically
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use rust_cli_roguelike::common::constants::GameConstants;
+use rust_cli_roguelike::common::protocol::{ClientMessage, PlayerId, ServerMessage};
+
+use crate::{Rooms, ServerGameState};
+
+pub type SharedRecorder = Arc<Mutex<Recorder>>;
+
+/// One line of a recording file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    pub elapsed_ms: u64, // Since the server started recording
+    #[serde(default = "default_room")]
+    pub room: String,
+    pub player_id: PlayerId,
+    pub message: ClientMessage,
+}
+
+/// Appends every client message the server handles to a JSON lines file
+#[derive(Debug)]
+pub struct Recorder {
+    file: File,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create recording {}: {}", path, e))?;
+        Ok(Recorder {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, room: &str, player_id: &PlayerId, message: &ClientMessage) {
+        let entry = RecordedMessage {
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            room: room.to_string(),
+            player_id: player_id.clone(),
+            message: message.clone(),
+        };
+
+        // Written line by line so a crash still leaves a usable recording
+        let result = serde_json::to_string(&entry)
+            .map_err(std::io::Error::from)
+            .and_then(|json| writeln!(self.file, "{}", json));
+        if let Err(e) = result {
+            log::error!("Failed to write recording: {}", e);
+        }
+    }
+}
+
+fn default_room() -> String {
+    GameConstants::DEFAULT_ROOM.to_string()
+}
+
+pub fn load_recording(path: &str) -> Result<Vec<RecordedMessage>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open recording {}: {}", path, e))?;
+
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|(i, line)| {
+            let line = line.map_err(|e| format!("Failed to read recording {}: {}", path, e))?;
+            serde_json::from_str(&line)
+                .map_err(|e| format!("Invalid recording entry on line {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+/// Feed a recording through fresh server states set up with the server's world
+/// options (`--spawn`, `--difficulty`, `--biomes`), printing what the server sends back
+pub fn replay(path: &str, options: &Rooms) -> Result<(), String> {
+    let recording = load_recording(path)?;
+    let mut rooms: HashMap<String, ServerGameState> = HashMap::new();
+    let mut senders = HashMap::new();
+    let mut receivers = HashMap::new();
+
+    println!("Replaying {} messages from {}", recording.len(), path);
+
+    for entry in recording {
+        let sender = senders.entry(entry.player_id.clone()).or_insert_with(|| {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            receivers.insert(entry.player_id.clone(), receiver);
+            sender
+        });
+
+        println!("[{:>8}ms] {}/{} <- {:?}", entry.elapsed_ms, entry.room, entry.player_id, entry.message);
+        // In-memory worlds start from the seed every time and never touch the saved ones
+        let state = rooms.entry(entry.room.clone()).or_insert_with(|| options.new_world(&entry.room));
+        state.handle_client_message(&entry.player_id, sender, entry.message);
+
+        for (player_id, receiver) in receivers.iter_mut() {
+            while let Ok(message) = receiver.try_recv() {
+                println!("{:>12} {} -> {}", "", player_id, describe(&message));
+            }
+        }
+    }
+
+    for (room, state) in &rooms {
+        println!("Room '{}' finished at turn {}", room, state.turn_count);
+        for player in state.players.values() {
+            println!("  {} ({}) at ({}, {}) in {:?}, HP {}/{}", player.name, player.id, player.x, player.y, player.current_map_type, player.hp, player.max_hp);
+        }
+    }
+    Ok(())
+}
+
+/// Short description of a server message; chunk and state payloads are too big to print
+fn describe(message: &ServerMessage) -> String {
+    match message {
+        ServerMessage::ChunkData { chunks } => format!("ChunkData ({} chunks)", chunks.len()),
+        ServerMessage::GameState { state } => format!("GameState (turn {}, {} players)", state.turn_count, state.players.len()),
+        ServerMessage::DungeonData { dungeon_map } => format!("DungeonData ({}x{})", dungeon_map.width, dungeon_map.height),
+        other => format!("{:?}", other),
+    }
+}